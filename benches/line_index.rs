@@ -0,0 +1,69 @@
+//! Line/column lookups on a large generated API client, as `cargo bench`
+//! prints them: the walk from the start of the file per offset against the
+//! per-file line index, and a full parse that uses the index.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use tta::line_index::LineIndex;
+use tta::parser::parse_source;
+use tta::shared_lib::byte_offset_to_line_col;
+
+/// A client the shape OpenAPI generators emit: a request/response pair and a
+/// union per endpoint.
+fn generated_client(endpoints: usize) -> String {
+    let mut code = String::new();
+    for i in 0..endpoints {
+        code.push_str(&format!(
+            "/** GET /resources/{i} — généré */\n\
+             export interface Resource{i}Request {{\n  id: string;\n  page?: number;\n  filter: Array<{{ key: string; value: string }}>;\n}}\n\n\
+             export interface Resource{i}Response {{\n  data: Resource{i}Request[];\n  total: number;\n}}\n\n\
+             export type Resource{i}Result = Resource{i}Response | {{ error: string }};\n\n"
+        ));
+    }
+    code
+}
+
+/// Runs `f` until a second has passed, returning the mean time per run.
+fn measure(mut f: impl FnMut()) -> Duration {
+    let started = Instant::now();
+    let mut runs = 0u32;
+    while runs == 0 || started.elapsed() < Duration::from_secs(1) {
+        f();
+        runs += 1;
+    }
+    started.elapsed() / runs
+}
+
+fn main() {
+    for endpoints in [200, 2_000] {
+        let code = generated_client(endpoints);
+        let offsets: Vec<usize> = code.match_indices("export ").map(|(i, _)| i).collect();
+        println!(
+            "{} endpoints, {} lines, {} declarations",
+            endpoints,
+            code.lines().count(),
+            offsets.len()
+        );
+
+        let walk = measure(|| {
+            for &offset in &offsets {
+                black_box(byte_offset_to_line_col(&code, offset));
+            }
+        });
+        println!("  walk per offset      {:>12.3?}", walk);
+
+        let indexed = measure(|| {
+            let lines = LineIndex::new(&code);
+            for &offset in &offsets {
+                black_box(lines.line_col(offset));
+            }
+        });
+        println!("  line index           {:>12.3?}", indexed);
+
+        let parse = measure(|| {
+            black_box(parse_source(&code, "client.ts", &[]));
+        });
+        println!("  parse_source         {:>12.3?}", parse);
+    }
+}
@@ -0,0 +1,631 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use crate::deep::Equivalence;
+use crate::files::{find_doc_files, find_source_files, read_source};
+use crate::imports::{resolve_declaration, ImportGraph, ModuleResolver};
+use crate::index::DeclarationIndex;
+use crate::parser::parse_source;
+use crate::shared_lib::{CheckerFactory, FoundDeclarationNode, Implementation};
+use crate::unused::{find_unused, UnusedDeclaration};
+use crate::usage::Usage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Severity {
+    /// Same name, different body.
+    Warning,
+    /// Same name and identical body.
+    Critical,
+}
+
+/// A pair of declarations sharing a name.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub name: String,
+    /// "type", "interface", a custom checker label, or "declaration" when the
+    /// two occurrences are of different kinds.
+    pub kind: String,
+    pub first: FoundDeclarationNode,
+    pub second: FoundDeclarationNode,
+    /// Classes implementing each of the two interfaces, for interfaces.
+    pub implementations: Option<Implementations>,
+    /// Sites referring to each of the two declarations.
+    pub usages: Usages,
+    /// Nested types matched up by a deep comparison, see [`crate::deep`].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub equivalences: Vec<Equivalence>,
+}
+
+impl Finding {
+    /// `(ambient, source)` when exactly one of the two copies comes from a
+    /// declaration file, e.g. a hand-written interface redeclaring a type a
+    /// `.d.ts` file already provides.
+    pub fn ambient_copy(&self) -> Option<(&FoundDeclarationNode, &FoundDeclarationNode)> {
+        match (self.first.is_ambient(), self.second.is_ambient()) {
+            (true, false) => Some((&self.first, &self.second)),
+            (false, true) => Some((&self.second, &self.first)),
+            _ => None,
+        }
+    }
+
+    /// `(documented, source)` when one of the two copies comes from a code
+    /// fence in the documentation and has drifted from the source.
+    pub fn documented_copy(&self) -> Option<(&FoundDeclarationNode, &FoundDeclarationNode)> {
+        match (self.first.is_documented(), self.second.is_documented()) {
+            (true, false) => Some((&self.first, &self.second)),
+            (false, true) => Some((&self.second, &self.first)),
+            _ => None,
+        }
+    }
+
+    /// One-line description of the finding, as the CLI and the language
+    /// server show it.
+    pub fn message(&self) -> String {
+        let (kind, name) = (&self.kind, &self.name);
+        match self.severity {
+            Severity::Critical => match self.ambient_copy() {
+                Some((ambient, _)) => format!(
+                    "{} '{}' redeclares a type from declaration file {}",
+                    kind, name, ambient.filename
+                ),
+                None if self.equivalences.is_empty() => {
+                    format!("Duplicate {} '{}' with identical body", kind, name)
+                }
+                None => format!(
+                    "Duplicate {} '{}' with structurally identical body",
+                    kind, name
+                ),
+            },
+            Severity::Warning => match (self.documented_copy(), self.ambient_copy()) {
+                (Some((documented, source)), _) => format!(
+                    "Documented {} '{}' in {} no longer matches {}",
+                    kind, name, documented.filename, source.filename
+                ),
+                (_, Some((ambient, _))) => format!(
+                    "{} '{}' differs from the declaration in {}",
+                    kind, name, ambient.filename
+                ),
+                // Only a deep comparison tells apart bodies with the same text.
+                _ if self.first.body == self.second.body => format!(
+                    "Duplicate {} '{}' with identical text referring to different types",
+                    kind, name
+                ),
+                _ => format!("Duplicate {} name '{}' with different body", kind, name),
+            },
+        }
+    }
+}
+
+/// `implements` clauses attributed to the declaration they resolve to.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Implementations {
+    pub first: Vec<Implementation>,
+    pub second: Vec<Implementation>,
+}
+
+/// Usage sites attributed to the declaration they resolve to.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Usages {
+    pub first: Vec<Usage>,
+    pub second: Vec<Usage>,
+}
+
+/// Incremental duplicate analysis over any mix of files on disk and in-memory
+/// sources.
+///
+/// ```no_run
+/// use tta::Analyzer;
+///
+/// let mut analyzer = Analyzer::new();
+/// for (file, err) in analyzer.add_path("src") {
+///     eprintln!("Skipped {}: {}", file, err);
+/// }
+/// analyzer.add_source("scratch.ts", "interface User { id: string }");
+/// for finding in analyzer.findings() {
+///     println!("{:?} {} {}", finding.severity, finding.kind, finding.name);
+/// }
+/// ```
+#[derive(Default)]
+pub struct Analyzer {
+    index: DeclarationIndex,
+    checkers: Vec<Box<dyn CheckerFactory>>,
+    resolver: ModuleResolver,
+    include_js: bool,
+    include_docs: bool,
+}
+
+impl Analyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also pick up `.js`/`.jsx`/`.mjs`/`.cjs` files in [`Analyzer::add_path`],
+    /// reading their JSDoc `@typedef`s and `@callback`s.
+    pub fn javascript(mut self, include_js: bool) -> Self {
+        self.include_js = include_js;
+        self
+    }
+
+    /// Also pick up `.md`/`.mdx` files in [`Analyzer::add_path`], checking the
+    /// types in their TypeScript code fences against the source.
+    pub fn docs(mut self, include_docs: bool) -> Self {
+        self.include_docs = include_docs;
+        self
+    }
+
+    /// How import specifiers are resolved, e.g. with a tsconfig's `paths`.
+    pub fn resolver(mut self, resolver: ModuleResolver) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    /// Registers an additional checker factory, consulted for declarations the
+    /// built-in type and interface checkers don't handle. Only affects sources
+    /// added afterwards.
+    pub fn with_checker(mut self, factory: impl CheckerFactory + 'static) -> Self {
+        self.checkers.push(Box::new(factory));
+        self
+    }
+
+    /// Adds (or replaces) an in-memory source. `filename` decides how the code
+    /// is parsed (`.ts` vs `.tsx`) and is reported in findings.
+    pub fn add_source(&mut self, filename: &str, code: &str) {
+        let parsed = parse_source(code, filename, &self.checkers);
+        self.index.insert(filename, parsed);
+    }
+
+    /// Reads and adds a file, or every source file below a directory:
+    /// `.ts`/`.tsx`/`.mts`/`.cts` (declaration files included) and Vue, Svelte
+    /// and Astro components, plus JavaScript with [`Analyzer::javascript`] and
+    /// Markdown with [`Analyzer::docs`]. Files that can't be read are skipped
+    /// and returned with their error.
+    pub fn add_path(&mut self, path: impl AsRef<Path>) -> Vec<(String, io::Error)> {
+        let path = path.as_ref();
+        let files = if path.is_dir() {
+            let mut files = find_source_files(path, self.include_js);
+            if self.include_docs {
+                files.extend(find_doc_files(path));
+            }
+            files
+        } else {
+            vec![path.to_string_lossy().to_string()]
+        };
+
+        let mut skipped = Vec::new();
+        for file in files {
+            match read_source(Path::new(&file)) {
+                Ok(code) => self.add_source(&file, &code),
+                Err(err) => skipped.push((file, err)),
+            }
+        }
+        skipped
+    }
+
+    pub fn remove_source(&mut self, filename: &str) {
+        self.index.remove_file(filename);
+    }
+
+    pub fn index(&self) -> &DeclarationIndex {
+        &self.index
+    }
+
+    /// All collected declarations, keyed by name.
+    pub fn declarations(&self) -> &HashMap<String, Vec<FoundDeclarationNode>> {
+        self.index.results()
+    }
+
+    pub fn findings(&self) -> Vec<Finding> {
+        self.findings_with_imports(&self.import_graph())
+    }
+
+    /// Like [`Analyzer::findings`], reusing an import graph the caller already built.
+    pub fn findings_with_imports(&self, imports: &ImportGraph) -> Vec<Finding> {
+        let implementations: Vec<Implementation> = self.index.implementations().cloned().collect();
+        let usages: Vec<Usage> = self.index.usages().cloned().collect();
+        find_duplicates(
+            self.index.results(),
+            &implementations,
+            &usages,
+            Some(imports),
+        )
+    }
+
+    /// Declarations nothing refers to, and exported ones no other file uses.
+    pub fn unused(&self) -> Vec<UnusedDeclaration> {
+        self.unused_with_imports(&self.import_graph())
+    }
+
+    /// Like [`Analyzer::unused`], reusing an import graph the caller already built.
+    pub fn unused_with_imports(&self, imports: &ImportGraph) -> Vec<UnusedDeclaration> {
+        let usages: Vec<Usage> = self.index.usages().cloned().collect();
+        find_unused(self.index.results(), &usages, Some(imports))
+    }
+
+    /// Resolves the imports of every source.
+    pub fn import_graph(&self) -> ImportGraph {
+        ImportGraph::build(&self.index, &self.resolver)
+    }
+}
+
+/// Pairs up every two declarations that share a name, ordered by name.
+/// Each `implements` clause and usage site is attributed to the declaration it
+/// resolves to (see [`resolve_declaration`]); ones that stay ambiguous are left
+/// out.
+pub fn find_duplicates(
+    results: &HashMap<String, Vec<FoundDeclarationNode>>,
+    implementations: &[Implementation],
+    usages: &[Usage],
+    imports: Option<&ImportGraph>,
+) -> Vec<Finding> {
+    let mut implemented_by: HashMap<(&str, usize), Vec<Implementation>> = HashMap::new();
+    for implementation in implementations {
+        if let Some(interface) = resolve_declaration(
+            results,
+            imports,
+            &implementation.filename,
+            &implementation.interface,
+        ) {
+            implemented_by
+                .entry((interface.filename.as_str(), interface.span_start))
+                .or_default()
+                .push(implementation.clone());
+        }
+    }
+    let implementations_of = |node: &FoundDeclarationNode| {
+        implemented_by
+            .get(&(node.filename.as_str(), node.span_start))
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    let mut used_by: HashMap<(&str, usize), Vec<Usage>> = HashMap::new();
+    for usage in usages {
+        // Only duplicated names need attributing.
+        if results.get(&usage.name).is_none_or(|found| found.len() < 2) {
+            continue;
+        }
+        let Some(declaration) = resolve_usage(results, imports, usage) else {
+            continue;
+        };
+        // A recursive type referring to itself is not a site to migrate.
+        let inside = declaration.filename == usage.filename
+            && (declaration.span_start..declaration.span_end).contains(&usage.span_start);
+        if !inside {
+            used_by
+                .entry((declaration.filename.as_str(), declaration.span_start))
+                .or_default()
+                .push(usage.clone());
+        }
+    }
+    let usages_of = |node: &FoundDeclarationNode| {
+        used_by
+            .get(&(node.filename.as_str(), node.span_start))
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    let mut names: Vec<&String> = results.keys().collect();
+    names.sort();
+
+    let mut findings = Vec::new();
+    for name in names {
+        let types = &results[name];
+        for i in 0..types.len() {
+            for j in (i + 1)..types.len() {
+                let type_a = &types[i];
+                let type_b = &types[j];
+
+                let severity = if type_a.body == type_b.body {
+                    Severity::Critical
+                } else {
+                    Severity::Warning
+                };
+
+                // Documented types only matter once they drift from the source.
+                let documented = type_a.is_documented() || type_b.is_documented();
+                let both_documented = type_a.is_documented() && type_b.is_documented();
+                if both_documented || (documented && severity == Severity::Critical) {
+                    continue;
+                }
+
+                let kind = if type_a.ast_node_variant == type_b.ast_node_variant {
+                    type_a.ast_node_variant.label().to_string()
+                } else {
+                    "declaration".to_string()
+                };
+
+                let implementations = (kind == "interface").then(|| Implementations {
+                    first: implementations_of(type_a),
+                    second: implementations_of(type_b),
+                });
+
+                findings.push(Finding {
+                    severity,
+                    name: name.clone(),
+                    kind,
+                    first: type_a.clone(),
+                    second: type_b.clone(),
+                    implementations,
+                    usages: Usages {
+                        first: usages_of(type_a),
+                        second: usages_of(type_b),
+                    },
+                    equivalences: Vec::new(),
+                });
+            }
+        }
+    }
+    findings
+}
+
+/// The declaration a usage site refers to, following `ns.Name` through
+/// `import * as ns`.
+pub(crate) fn resolve_usage<'d>(
+    results: &'d HashMap<String, Vec<FoundDeclarationNode>>,
+    imports: Option<&ImportGraph>,
+    usage: &Usage,
+) -> Option<&'d FoundDeclarationNode> {
+    match &usage.qualifier {
+        None => resolve_declaration(results, imports, &usage.filename, &usage.name),
+        Some(namespace) => {
+            let (origin, name) =
+                imports?.resolve_namespace_member(&usage.filename, namespace, &usage.name)?;
+            results.get(&name)?.iter().find(|d| d.filename == origin)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared_lib::{
+        byte_offset_to_line_col, AstNodeVariant, DeclarationChecker, FoundDeclarationNode,
+    };
+    use oxc::ast::ast::{Declaration, TSEnumDeclaration};
+    use oxc::span::Span;
+
+    struct EnumChecker<'a> {
+        enum_decl: &'a TSEnumDeclaration<'a>,
+    }
+
+    impl DeclarationChecker for EnumChecker<'_> {
+        fn from_ast(
+            &self,
+            source: &str,
+            filename: &str,
+            is_exported: bool,
+            override_span: Option<Span>,
+        ) -> FoundDeclarationNode {
+            let span = override_span.unwrap_or(self.enum_decl.span);
+            let (line, col) = byte_offset_to_line_col(source, span.start as usize);
+            let members: Vec<String> = self
+                .enum_decl
+                .body
+                .members
+                .iter()
+                .map(|m| m.id.static_name().to_string())
+                .collect();
+            FoundDeclarationNode {
+                ast_node_variant: AstNodeVariant::Custom("enum".to_string()),
+                name: self.enum_decl.id.name.to_string(),
+                filename: filename.to_string(),
+                line,
+                col,
+                span_start: span.start as usize,
+                span_end: span.end as usize,
+                is_exported,
+                body: members.join(", "),
+                references: Vec::new(),
+            }
+        }
+    }
+
+    struct EnumCheckerFactory;
+
+    impl CheckerFactory for EnumCheckerFactory {
+        fn checker<'a>(
+            &self,
+            declaration: &'a Declaration<'a>,
+        ) -> Option<Box<dyn DeclarationChecker + 'a>> {
+            match declaration {
+                Declaration::TSEnumDeclaration(enum_decl) => {
+                    Some(Box::new(EnumChecker { enum_decl }))
+                }
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_analyzer_in_memory_sources() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a.ts", "interface IFoo { x: string; }");
+        analyzer.add_source("b.ts", "interface IFoo { x: string; }");
+        analyzer.add_source("c.ts", "type Foo = string;");
+
+        let findings = analyzer.findings();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert_eq!(findings[0].kind, "interface");
+        let implementations = findings[0].implementations.as_ref().unwrap();
+        assert!(implementations.first.is_empty() && implementations.second.is_empty());
+    }
+
+    #[test]
+    fn test_analyzer_warning_for_different_bodies() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a.ts", "type Foo = string;");
+        analyzer.add_source("b.ts", "interface Foo { x: string; }");
+
+        let findings = analyzer.findings();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert_eq!(findings[0].kind, "declaration");
+        assert!(findings[0].implementations.is_none());
+    }
+
+    #[test]
+    fn test_implementations_attributed_per_declaration() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a.ts", "export interface IFoo { x: string; }");
+        analyzer.add_source("b.ts", "export interface IFoo { y: number; }");
+        analyzer.add_source(
+            "impl.ts",
+            "import { IFoo } from './a';\nimport { IFoo as Other } from './b';\n\
+             class A implements IFoo { x = 'a'; }\nclass B implements IFoo { x = 'b'; }\n\
+             class C implements Other { y = 1; }",
+        );
+
+        let findings = analyzer.findings();
+        assert_eq!(findings.len(), 1);
+        let implementations = findings[0].implementations.as_ref().unwrap();
+        let (for_a, for_b) = if findings[0].first.filename == "a.ts" {
+            (&implementations.first, &implementations.second)
+        } else {
+            (&implementations.second, &implementations.first)
+        };
+
+        let names = |list: &[Implementation]| -> Vec<String> {
+            list.iter().map(|i| i.class_name.clone()).collect()
+        };
+        assert_eq!(names(for_a), vec!["A", "B"]);
+        assert_eq!(names(for_b), vec!["C"]);
+        assert_eq!(for_b[0].filename, "impl.ts");
+        assert_eq!(for_b[0].line, 5);
+    }
+
+    #[test]
+    fn test_usages_attributed_per_declaration() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a.ts", "export interface IFoo { x: string; }");
+        analyzer.add_source("b.ts", "export interface IFoo { x: string; }");
+        analyzer.add_source(
+            "use.ts",
+            "import * as a from './a';\nimport { IFoo } from './b';\n\
+             const x: a.IFoo = { x: '' };\nconst y = {} as IFoo;\n\
+             function f(foo: IFoo): Array<IFoo> { return [foo]; }",
+        );
+
+        let findings = analyzer.findings();
+        let usages = &findings[0].usages;
+        let (for_a, for_b) = if findings[0].first.filename == "a.ts" {
+            (&usages.first, &usages.second)
+        } else {
+            (&usages.second, &usages.first)
+        };
+        assert_eq!(for_a.len(), 1);
+        assert_eq!(for_a[0].line, 3);
+        assert_eq!(for_b.len(), 3);
+    }
+
+    #[test]
+    fn test_ambient_copy_from_declaration_file() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("types/api.d.ts", "interface User { id: string }");
+        analyzer.add_source("src/user.mts", "interface User { id: string }");
+        analyzer.add_source("src/other.cts", "interface Other { id: string }");
+        analyzer.add_source("types/more.d.ts", "interface Other { id: string }");
+        analyzer.add_source("types/extra.d.ts", "interface Other { id: string }");
+
+        let findings = analyzer.findings();
+        let user = findings.iter().find(|f| f.name == "User").unwrap();
+        let (ambient, source) = user.ambient_copy().unwrap();
+        assert_eq!(ambient.filename, "types/api.d.ts");
+        assert_eq!(source.filename, "src/user.mts");
+
+        let both_ambient = findings
+            .iter()
+            .filter(|f| f.first.is_ambient() && f.second.is_ambient())
+            .count();
+        assert_eq!(both_ambient, 1);
+    }
+
+    #[test]
+    fn test_component_script_blocks() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source(
+            "src/Card.vue",
+            "<template>\n  <div>{{ title }}</div>\n</template>\n\n<script setup lang=\"ts\">\ninterface Props { title: string }\n</script>\n",
+        );
+        analyzer.add_source("src/props.ts", "interface Props { title: string }");
+
+        let findings = analyzer.findings();
+        assert_eq!(findings.len(), 1);
+        let vue = [&findings[0].first, &findings[0].second]
+            .into_iter()
+            .find(|node| node.filename == "src/Card.vue")
+            .unwrap();
+        assert_eq!(vue.line, 6);
+        assert_eq!(vue.col, 1);
+    }
+
+    #[test]
+    fn test_documented_types_report_only_drift() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source(
+            "docs/users.md",
+            "# Users\n\n```ts\ninterface User { id: string }\n```\n\n```ts\ntype Id = string;\n```\n",
+        );
+        analyzer.add_source("guide.mdx", "```typescript\ntype Id = string;\n```\n");
+        analyzer.add_source(
+            "src/user.ts",
+            "interface User { id: number }\ntype Id = string;",
+        );
+
+        let findings = analyzer.findings();
+        assert_eq!(findings.len(), 1);
+        let (documented, source) = findings[0].documented_copy().unwrap();
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert_eq!(documented.filename, "docs/users.md");
+        assert_eq!(documented.line, 4);
+        assert_eq!(source.filename, "src/user.ts");
+    }
+
+    #[test]
+    fn test_analyzer_remove_source() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a.ts", "type Foo = string;");
+        analyzer.add_source("b.ts", "type Foo = string;");
+        analyzer.remove_source("b.ts");
+        assert!(analyzer.findings().is_empty());
+    }
+
+    #[test]
+    fn test_add_path_skips_unreadable_files() {
+        let dir = crate::test_support::temp_tree(&[
+            ("a.ts", "interface User { id: string }"),
+            ("c.ts", "interface User { id: string }"),
+        ]);
+        std::fs::write(dir.path().join("b.ts"), b"interface User { id: \xFF }").unwrap();
+
+        let mut analyzer = Analyzer::new();
+        let skipped = analyzer.add_path(dir.path());
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].0.ends_with("b.ts"));
+        assert_eq!(analyzer.findings().len(), 1);
+    }
+
+    #[test]
+    fn test_analyzer_custom_checker() {
+        let mut analyzer = Analyzer::new().with_checker(EnumCheckerFactory);
+        analyzer.add_source("a.ts", "enum Color { Red, Green }");
+        analyzer.add_source("b.ts", "export enum Color { Red, Green }");
+
+        let findings = analyzer.findings();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, "enum");
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert!(findings[0].second.is_exported);
+    }
+
+    #[test]
+    fn test_analyzer_without_custom_checker_ignores_enums() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a.ts", "enum Color { Red }");
+        analyzer.add_source("b.ts", "enum Color { Red }");
+        assert!(analyzer.declarations().is_empty());
+    }
+}
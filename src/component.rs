@@ -0,0 +1,248 @@
+use oxc::span::SourceType;
+use std::borrow::Cow;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::files::is_component_file;
+
+/// The TypeScript parts of a single-file component: `<script lang="ts">`
+/// blocks in Vue and Svelte files, and the frontmatter plus `<script>` blocks
+/// (TypeScript by default, except `is:inline`) in Astro files. Ranges are byte
+/// offsets of the block contents, in source order.
+pub fn script_blocks(code: &str, filename: &str) -> Vec<Range<usize>> {
+    typescript_blocks(code, filename)
+        .into_iter()
+        .map(|block| block.range)
+        .collect()
+}
+
+/// One block found by [`script_blocks`].
+struct ScriptBlock {
+    range: Range<usize>,
+    /// Declared with `lang="tsx"` on its `<script>` tag.
+    tsx: bool,
+}
+
+fn typescript_blocks(code: &str, filename: &str) -> Vec<ScriptBlock> {
+    let path = Path::new(filename);
+    if !is_component_file(path) {
+        return Vec::new();
+    }
+    let astro = path.extension().is_some_and(|ext| ext == "astro");
+
+    let mut blocks = Vec::new();
+    if astro {
+        blocks.extend(frontmatter(code).map(|range| ScriptBlock { range, tsx: false }));
+    }
+
+    let mut from = 0;
+    while let Some(found) = code[from..].find("<script") {
+        let tag_start = from + found;
+        let Some(tag_len) = tag_len(&code[tag_start..]) else {
+            break;
+        };
+        let attributes = &code[tag_start + "<script".len()..tag_start + tag_len];
+        let content_start = tag_start + tag_len + 1;
+        let content_end = code[content_start..]
+            .find("</script")
+            .map_or(code.len(), |end| content_start + end);
+        from = content_end;
+
+        // `<scripts>` or the like isn't a script tag.
+        if !attributes.is_empty() && !attributes.starts_with(char::is_whitespace) {
+            continue;
+        }
+        let lang = attribute(attributes, "lang");
+        let is_typescript = if astro {
+            !attributes.contains("is:inline")
+        } else {
+            matches!(lang, Some("ts" | "tsx" | "typescript"))
+        };
+        if is_typescript && !attributes.ends_with('/') {
+            blocks.push(ScriptBlock {
+                range: content_start..content_end,
+                tsx: lang == Some("tsx"),
+            });
+        }
+    }
+    blocks
+}
+
+/// Offset of the `>` closing the tag `tag` starts with. Quoted attribute
+/// values may contain `>`, as in Vue's `generic="T extends Array<string>"`.
+fn tag_len(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut after_equals = false;
+    for (i, ch) in tag.char_indices() {
+        if let Some(open) = quote {
+            if ch == open {
+                quote = None;
+            }
+            continue;
+        }
+        match ch {
+            '>' => return Some(i),
+            '"' | '\'' if after_equals => quote = Some(ch),
+            _ => {}
+        }
+        after_equals = ch == '=' || (after_equals && ch.is_whitespace());
+    }
+    None
+}
+
+/// What oxc parses for `filename`: the code itself, or for a component only
+/// its script blocks with everything else blanked out, so offsets, lines and
+/// columns still point into the original file.
+pub fn parse_input<'a>(code: &'a str, filename: &str) -> (Cow<'a, str>, SourceType) {
+    if !is_component_file(Path::new(filename)) {
+        let source_type = SourceType::from_path(filename).unwrap_or_default();
+        return (Cow::Borrowed(code), source_type);
+    }
+
+    let blocks = typescript_blocks(code, filename);
+    let mut masked = String::with_capacity(code.len());
+    let mut last = 0;
+    for block in &blocks {
+        masked.extend(blank(&code[last..block.range.start]));
+        masked.push_str(&code[block.range.clone()]);
+        last = block.range.end;
+    }
+    masked.extend(blank(&code[last..]));
+
+    let source_type = if blocks.iter().any(|block| block.tsx) {
+        SourceType::tsx()
+    } else {
+        SourceType::ts()
+    };
+    (Cow::Owned(masked), source_type)
+}
+
+/// Whitespace in place of `text`, keeping its line breaks. Every character is
+/// replaced by whitespace of the same UTF-8 length and UTF-16 width, so byte
+/// offsets and UTF-16 columns of the code after it stay the same.
+pub(crate) fn blank(text: &str) -> impl Iterator<Item = char> + '_ {
+    text.chars().flat_map(|ch| {
+        let padding: &[char] = match ch {
+            '\n' => &['\n'],
+            '\r' => &['\r'],
+            _ => match ch.len_utf8() {
+                1 => &[' '],
+                2 => &['\u{a0}'],
+                3 => &['\u{3000}'],
+                // Outside the BMP: two UTF-16 units, so two 2-byte spaces.
+                _ => &['\u{a0}', '\u{a0}'],
+            },
+        };
+        padding.iter().copied()
+    })
+}
+
+/// The contents of a leading `---` fenced block.
+fn frontmatter(code: &str) -> Option<Range<usize>> {
+    let start = code.len() - code.trim_start().len();
+    let rest = code[start..].strip_prefix("---")?;
+    let content_start = code.len() - rest.len();
+    let end = rest.find("\n---")?;
+    Some(content_start..content_start + end + 1)
+}
+
+/// The value of `name="value"` (or single-quoted) among tag attributes.
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+    while let Some(at) = rest.find(name) {
+        let preceded_by_space = rest[..at].ends_with(char::is_whitespace);
+        let after = rest[at + name.len()..].trim_start();
+        rest = &rest[at + name.len()..];
+        let Some(value) = after.strip_prefix('=') else {
+            continue;
+        };
+        if !preceded_by_space {
+            continue;
+        }
+        let value = value.trim_start();
+        let quote = value.chars().next()?;
+        if quote == '"' || quote == '\'' {
+            let value = &value[1..];
+            return value.find(quote).map(|end| &value[..end]);
+        }
+        let end = value
+            .find(|ch: char| ch.is_whitespace() || ch == '/')
+            .unwrap_or(value.len());
+        return Some(&value[..end]);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_index::LineIndex;
+
+    #[test]
+    fn test_script_blocks() {
+        let vue = "<template><div /></template>\n<script setup lang=\"ts\">\ninterface Props { a: string }\n</script>\n<script>\nconst x = 1;\n</script>\n";
+        let blocks = script_blocks(vue, "Comp.vue");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(&vue[blocks[0].clone()], "\ninterface Props { a: string }\n");
+
+        let svelte = "<script context=\"module\" lang='ts'>\ntype A = string;\n</script>";
+        assert_eq!(script_blocks(svelte, "A.svelte").len(), 1);
+
+        let astro = "---\ninterface Props { title: string }\n---\n<h1>Hi</h1>\n<script>\ntype B = number;\n</script>\n<script is:inline>var c = 1;</script>\n";
+        let blocks = script_blocks(astro, "Page.astro");
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(
+            &astro[blocks[0].clone()],
+            "\ninterface Props { title: string }\n"
+        );
+        assert_eq!(&astro[blocks[1].clone()], "\ntype B = number;\n");
+    }
+
+    #[test]
+    fn test_generic_attribute_with_angle_brackets() {
+        let vue = "<script setup lang=\"ts\" generic=\"T extends Array<string>\">\ninterface Props { items: T }\n</script>\n";
+        let blocks = script_blocks(vue, "List.vue");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(&vue[blocks[0].clone()], "\ninterface Props { items: T }\n");
+
+        let parsed = crate::parser::parse_source(vue, "List.vue", &[]);
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.declarations[0].name, "Props");
+    }
+
+    #[test]
+    fn test_parse_input_keeps_offsets() {
+        let vue = "<template>\n  <p>é</p>\n</template>\n<script lang=\"ts\">\ntype A = string;\n</script>\n";
+        let (masked, _) = parse_input(vue, "Comp.vue");
+        assert_eq!(masked.len(), vue.len());
+        assert_eq!(masked.lines().count(), vue.lines().count());
+        let at = vue.find("type A").unwrap();
+        assert_eq!(&masked[at..at + 16], "type A = string;");
+        assert!(!masked.contains("template"));
+    }
+
+    #[test]
+    fn test_blank_keeps_columns_after_multibyte_text() {
+        let vue = "<p>é 中 🎉</p><script lang=\"ts\">type A = string;</script>\n";
+        let (masked, _) = parse_input(vue, "Comp.vue");
+        let at = vue.find("type A").unwrap();
+        assert_eq!(masked.len(), vue.len());
+        assert_eq!(&masked[at..at + 16], "type A = string;");
+        assert!(masked[..at].trim().is_empty());
+
+        let utf16 = |text: &str| LineIndex::new(text).line_col_utf16(at);
+        assert_eq!(utf16(&masked), utf16(vue));
+
+        let parsed = crate::parser::parse_source(vue, "Comp.vue", &[]);
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.declarations[0].col, vue[..at].chars().count() + 1);
+    }
+
+    #[test]
+    fn test_tsx_only_from_script_lang() {
+        let vue = "<template><p>lang=\"tsx\"</p></template>\n<script lang=\"ts\">type A = string;</script>\n";
+        assert!(!parse_input(vue, "Comp.vue").1.is_jsx());
+        let tsx = "<script setup lang='tsx'>type A = string;</script>\n";
+        assert!(parse_input(tsx, "Comp.vue").1.is_jsx());
+    }
+}
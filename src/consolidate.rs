@@ -0,0 +1,692 @@
+use oxc::allocator::Allocator;
+use oxc::ast::ast::{ImportDeclarationSpecifier, Statement};
+use oxc::parser::Parser as OxcParser;
+use oxc::span::{GetSpan, SourceType};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use crate::analyzer::{Finding, Severity};
+use crate::component::{parse_input, script_blocks};
+use crate::deep::{is_identifier_char, DeepComparer};
+use crate::imports::ModuleResolver;
+use crate::shared_lib::{AstNodeVariant, FoundDeclarationNode};
+use crate::workspace::Workspace;
+
+/// How the occurrence that survives a consolidation is picked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum CanonicalStrategy {
+    /// The copy with the most usage sites.
+    #[default]
+    MostUsed,
+    /// An exported copy, so no new export is needed.
+    Exported,
+    /// A copy in the workspace library best suited to host shared code.
+    SharedLib,
+}
+
+impl FromStr for CanonicalStrategy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "most-used" => Ok(CanonicalStrategy::MostUsed),
+            "exported" => Ok(CanonicalStrategy::Exported),
+            "shared-lib" => Ok(CanonicalStrategy::SharedLib),
+            other => Err(format!(
+                "unknown strategy '{}', expected most-used, exported or shared-lib",
+                other
+            )),
+        }
+    }
+}
+
+/// A declaration to delete in favour of the canonical copy.
+#[derive(Debug, Clone, Serialize)]
+pub struct Removal {
+    pub declaration: FoundDeclarationNode,
+    /// Whether its own file refers to it, so the file needs an import.
+    pub used_locally: bool,
+}
+
+/// A set of identical declarations collapsed into one.
+#[derive(Debug, Clone, Serialize)]
+pub struct Consolidation {
+    pub name: String,
+    pub canonical: FoundDeclarationNode,
+    pub removed: Vec<Removal>,
+}
+
+/// A replacement of `start..end` in one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TextEdit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// Applies `edits` to `source`. Insertions may share a position; edits whose
+/// ranges overlap, or don't fall on character boundaries within `source` (as
+/// with a plan made for an older version of the file), are rejected rather
+/// than guessed at.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> Result<String, String> {
+    let mut edits: Vec<&TextEdit> = edits.iter().collect();
+    edits.sort_by_key(|edit| (edit.start, edit.end));
+
+    let mut out = String::with_capacity(source.len());
+    let mut cursor = 0;
+    for (i, edit) in edits.iter().enumerate() {
+        if edit.start < cursor {
+            let previous = edits[i - 1];
+            return Err(format!(
+                "overlapping edits at {}..{} and {}..{}",
+                previous.start, previous.end, edit.start, edit.end
+            ));
+        }
+        if source.get(edit.start..edit.end).is_none() {
+            return Err(format!(
+                "edit at {}..{} does not fit the source, which may have changed",
+                edit.start, edit.end
+            ));
+        }
+        out.push_str(&source[cursor..edit.start]);
+        out.push_str(&edit.text);
+        cursor = edit.end;
+    }
+    out.push_str(&source[cursor..]);
+    Ok(out)
+}
+
+/// Groups the critical findings into sets of identical declarations and picks
+/// the copy each set keeps. Ordered by name. Pairs whose identical text refers
+/// to types that `comparer` doesn't find equivalent are left out, since
+/// replacing one copy with the other would change what it means.
+pub fn plan_consolidations(
+    findings: &[Finding],
+    strategy: CanonicalStrategy,
+    workspace: Option<&Workspace>,
+    comparer: &DeepComparer,
+) -> Vec<Consolidation> {
+    type Key = (String, usize);
+    let key = |node: &FoundDeclarationNode| (node.filename.clone(), node.span_start);
+
+    // Pairs are transitive for identical bodies, so sets are merged as they meet.
+    let mut groups: BTreeMap<String, Vec<Vec<Key>>> = BTreeMap::new();
+    let mut nodes: HashMap<Key, &FoundDeclarationNode> = HashMap::new();
+    let mut usage_counts: HashMap<Key, (usize, usize)> = HashMap::new();
+
+    for finding in findings {
+        if finding.severity != Severity::Critical
+            || comparer
+                .equivalent(&finding.first, &finding.second)
+                .is_none()
+        {
+            continue;
+        }
+        let pairs = [
+            (&finding.first, &finding.usages.first),
+            (&finding.second, &finding.usages.second),
+        ];
+        for (node, usages) in pairs {
+            nodes.insert(key(node), node);
+            let local = usages
+                .iter()
+                .filter(|usage| usage.filename == node.filename)
+                .count();
+            usage_counts.insert(key(node), (usages.len(), local));
+        }
+
+        let sets = groups.entry(finding.name.clone()).or_default();
+        let (a, b) = (key(&finding.first), key(&finding.second));
+        let found_a = sets.iter().position(|set| set.contains(&a));
+        let found_b = sets.iter().position(|set| set.contains(&b));
+        match (found_a, found_b) {
+            (Some(i), Some(j)) if i != j => {
+                let merged = sets.remove(i.max(j));
+                sets[i.min(j)].extend(merged);
+            }
+            (Some(_), Some(_)) => {}
+            (Some(i), None) => sets[i].push(b),
+            (None, Some(j)) => sets[j].push(a),
+            (None, None) => sets.push(vec![a, b]),
+        }
+    }
+
+    let mut consolidations = Vec::new();
+    for (name, sets) in groups {
+        for set in sets {
+            let mut members: Vec<&FoundDeclarationNode> = set.iter().map(|k| nodes[k]).collect();
+            members.sort_by(|a, b| (&a.filename, a.span_start).cmp(&(&b.filename, b.span_start)));
+
+            let used = |node: &FoundDeclarationNode| usage_counts[&key(node)].0;
+            let host = match (strategy, workspace) {
+                (CanonicalStrategy::SharedLib, Some(workspace)) => workspace
+                    .suggest_host(members.iter().map(|node| node.filename.as_str()))
+                    .map(|host| host.root.clone()),
+                _ => None,
+            };
+            let in_host = |node: &FoundDeclarationNode| {
+                let (Some(workspace), Some(host)) = (workspace, &host) else {
+                    return false;
+                };
+                workspace
+                    .project_for(&node.filename)
+                    .is_some_and(|project| &project.root == host)
+            };
+
+            // `max_by_key` keeps the last maximum; iterate in reverse so ties
+            // go to the first copy by path.
+            let canonical = *members
+                .iter()
+                .rev()
+                .max_by_key(|node| match strategy {
+                    CanonicalStrategy::MostUsed => (false, used(node), node.is_exported),
+                    CanonicalStrategy::Exported => (node.is_exported, used(node), false),
+                    CanonicalStrategy::SharedLib => (in_host(node), used(node), node.is_exported),
+                })
+                .expect("a set has at least two members");
+
+            let removed = members
+                .iter()
+                .filter(|node| key(node) != key(canonical))
+                .map(|node| Removal {
+                    declaration: (*node).clone(),
+                    used_locally: usage_counts[&key(node)].1 > 0,
+                })
+                .collect();
+            consolidations.push(Consolidation {
+                name: name.clone(),
+                canonical: canonical.clone(),
+                removed,
+            });
+        }
+    }
+    consolidations
+}
+
+/// Leaves global declaration files alone: `.d.ts` files without a top-level
+/// import or export, whose declarations are visible everywhere and which an
+/// added `export` or `import type` would turn into modules. Copies in them are
+/// kept, and sets whose canonical copy is in one are dropped. `source`
+/// returns a file's current text.
+pub fn skip_global_scripts<'s>(
+    consolidations: Vec<Consolidation>,
+    source: impl Fn(&str) -> Option<&'s str>,
+) -> Vec<Consolidation> {
+    let mut global: HashMap<String, bool> = HashMap::new();
+    let mut is_global = |node: &FoundDeclarationNode| {
+        *global
+            .entry(node.filename.clone())
+            .or_insert_with(|| node.is_ambient() && source(&node.filename).is_some_and(is_script))
+    };
+
+    consolidations
+        .into_iter()
+        .filter_map(|mut consolidation| {
+            if is_global(&consolidation.canonical) {
+                return None;
+            }
+            consolidation
+                .removed
+                .retain(|removal| !is_global(&removal.declaration));
+            (!consolidation.removed.is_empty()).then_some(consolidation)
+        })
+        .collect()
+}
+
+/// Whether `code` has no top-level import or export, i.e. is a script.
+fn is_script(code: &str) -> bool {
+    let allocator = Allocator::default();
+    let parsed = OxcParser::new(&allocator, code, SourceType::d_ts()).parse();
+    !parsed
+        .program
+        .body
+        .iter()
+        .any(|stmt| stmt.as_module_declaration().is_some())
+}
+
+/// The edits carrying out `consolidations`, per file: removed declarations are
+/// deleted along with imports only they used, their files import (and, when
+/// they exported it, re-export) the canonical copy with `import type`, and a
+/// canonical copy that wasn't exported gains `export`. `source` returns a
+/// file's current text.
+pub fn consolidation_edits<'s>(
+    consolidations: &[Consolidation],
+    resolver: &ModuleResolver,
+    source: impl Fn(&str) -> Option<&'s str>,
+) -> BTreeMap<String, Vec<TextEdit>> {
+    let mut edits: BTreeMap<String, Vec<TextEdit>> = BTreeMap::new();
+    let mut imports: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    // Spans of the deleted declarations and the names they referred to.
+    let mut deleted: BTreeMap<String, Vec<(usize, usize)>> = BTreeMap::new();
+    let mut deleted_references: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for consolidation in consolidations {
+        let canonical = &consolidation.canonical;
+        // JSDoc typedefs are exported by the module they are in.
+        let typedef = canonical.ast_node_variant == AstNodeVariant::Typedef;
+        if !canonical.is_exported && !typedef {
+            edits
+                .entry(canonical.filename.clone())
+                .or_default()
+                .push(TextEdit {
+                    start: canonical.span_start,
+                    end: canonical.span_start,
+                    text: "export ".to_string(),
+                });
+        }
+
+        for removal in &consolidation.removed {
+            let node = &removal.declaration;
+            let Some(code) = source(&node.filename) else {
+                continue;
+            };
+            // A removed typedef becomes one pointing at the canonical copy,
+            // the JSDoc form of an import.
+            if node.ast_node_variant == AstNodeVariant::Typedef {
+                let specifier = resolver.specifier(&node.filename, &canonical.filename);
+                edits
+                    .entry(node.filename.clone())
+                    .or_default()
+                    .push(TextEdit {
+                        start: node.span_start,
+                        end: node.span_end,
+                        text: format!(
+                            "@typedef {{import('{}').{}}} {}",
+                            specifier, consolidation.name, consolidation.name
+                        ),
+                    });
+                continue;
+            }
+            let mut end = node.span_end;
+            if code[end..].starts_with('\n') {
+                end += 1;
+            }
+            edits
+                .entry(node.filename.clone())
+                .or_default()
+                .push(TextEdit {
+                    start: node.span_start,
+                    end,
+                    text: String::new(),
+                });
+            deleted
+                .entry(node.filename.clone())
+                .or_default()
+                .push((node.span_start, node.span_end));
+            deleted_references
+                .entry(node.filename.clone())
+                .or_default()
+                .extend(node.references.iter().cloned());
+            imports.entry(node.filename.clone()).or_default();
+
+            // A copy in the canonical file itself just goes away.
+            if node.filename == canonical.filename {
+                continue;
+            }
+            let specifier = resolver.specifier(&node.filename, &canonical.filename);
+            let name = &consolidation.name;
+            let lines = imports.entry(node.filename.clone()).or_default();
+            match (removal.used_locally, node.is_exported) {
+                (true, true) => {
+                    lines.push(format!("import type {{ {} }} from '{}';", name, specifier));
+                    lines.push(format!("export type {{ {} }};", name));
+                }
+                (true, false) => {
+                    lines.push(format!("import type {{ {} }} from '{}';", name, specifier))
+                }
+                (false, true) => {
+                    lines.push(format!("export type {{ {} }} from '{}';", name, specifier))
+                }
+                (false, false) => {}
+            }
+        }
+    }
+
+    for (filename, lines) in imports {
+        let Some(code) = source(&filename) else {
+            continue;
+        };
+        let spans = deleted.remove(&filename).unwrap_or_default();
+        let names = deleted_references.remove(&filename).unwrap_or_default();
+        let (pruned, at) = prune_imports(code, &filename, &spans, &names);
+        edits.entry(filename.clone()).or_default().extend(pruned);
+        if lines.is_empty() {
+            continue;
+        }
+        let text = if at == 0 {
+            format!("{}\n", lines.join("\n"))
+        } else {
+            format!("\n{}", lines.join("\n"))
+        };
+        edits.entry(filename).or_default().push(TextEdit {
+            start: at,
+            end: at,
+            text,
+        });
+    }
+    edits
+}
+
+/// Edits deleting the imports of `names` that nothing but the `deleted`
+/// declarations mentions, and where new imports go: just after the last
+/// top-level import that is kept, or the start of the file (of the first
+/// script block, in a component).
+fn prune_imports(
+    code: &str,
+    filename: &str,
+    deleted: &[(usize, usize)],
+    names: &[String],
+) -> (Vec<TextEdit>, usize) {
+    let allocator = Allocator::default();
+    let (masked, source_type) = parse_input(code, filename);
+    let parsed = OxcParser::new(&allocator, &masked, source_type).parse();
+
+    let mut edits = Vec::new();
+    let mut insertion_point = None;
+    for stmt in &parsed.program.body {
+        let Statement::ImportDeclaration(import) = stmt else {
+            continue;
+        };
+        let (start, end) = (import.span.start as usize, import.span.end as usize);
+        let specifiers: Vec<&ImportDeclarationSpecifier> =
+            import.specifiers.iter().flatten().collect();
+        let unused: Vec<bool> = specifiers
+            .iter()
+            .map(|specifier| {
+                let local = specifier.local().name.as_str();
+                names.iter().any(|name| name == local)
+                    && !mentioned(&masked, local, deleted, (start, end))
+            })
+            .collect();
+
+        // Side-effect imports have no specifiers and are always kept.
+        if !specifiers.is_empty() && unused.iter().all(|&unused| unused) {
+            let mut end = end;
+            if code[end..].starts_with('\n') {
+                end += 1;
+            }
+            edits.push(TextEdit {
+                start,
+                end,
+                text: String::new(),
+            });
+            continue;
+        }
+        insertion_point = Some(end);
+
+        // Only `{ ... }` lists lose single entries; the kept ones are joined
+        // back together from their own text.
+        let named: Vec<(&ImportDeclarationSpecifier, bool)> = specifiers
+            .iter()
+            .zip(&unused)
+            .filter(|(specifier, _)| {
+                matches!(specifier, ImportDeclarationSpecifier::ImportSpecifier(_))
+            })
+            .map(|(specifier, &unused)| (*specifier, unused))
+            .collect();
+        if !named.iter().any(|&(_, unused)| unused) {
+            continue;
+        }
+        let list_start = named[0].0.span().start as usize;
+        let list_end = named[named.len() - 1].0.span().end as usize;
+        let kept: Vec<&str> = named
+            .iter()
+            .filter(|&&(_, unused)| !unused)
+            .map(|(specifier, _)| {
+                &code[specifier.span().start as usize..specifier.span().end as usize]
+            })
+            .collect();
+        if !kept.is_empty() {
+            edits.push(TextEdit {
+                start: list_start,
+                end: list_end,
+                text: kept.join(", "),
+            });
+            continue;
+        }
+        // `import D, { A }` with only `A` unused: drop `, { A }`.
+        let before = specifiers
+            .iter()
+            .take_while(|specifier| {
+                !matches!(specifier, ImportDeclarationSpecifier::ImportSpecifier(_))
+            })
+            .last()
+            .map_or(list_start, |specifier| specifier.span().end as usize);
+        let close = code[list_end..end]
+            .find('}')
+            .map_or(list_end, |at| list_end + at + 1);
+        edits.push(TextEdit {
+            start: before,
+            end: close,
+            text: String::new(),
+        });
+    }
+
+    let insertion_point = insertion_point.unwrap_or_else(|| {
+        script_blocks(code, filename)
+            .first()
+            .map_or(0, |block| block.start)
+    });
+    (edits, insertion_point)
+}
+
+/// Whether `name` occurs as a whole word in `code` outside the `deleted`
+/// spans and the import statement at `import`. Comments and strings count, so
+/// doubt keeps an import.
+fn mentioned(code: &str, name: &str, deleted: &[(usize, usize)], import: (usize, usize)) -> bool {
+    let outside = |at: usize, (start, end): (usize, usize)| at < start || at >= end;
+    code.match_indices(name).any(|(at, _)| {
+        let before = code[..at].chars().next_back();
+        let after = code[at + name.len()..].chars().next();
+        !before.is_some_and(is_identifier_char)
+            && !after.is_some_and(is_identifier_char)
+            && outside(at, import)
+            && deleted.iter().all(|&span| outside(at, span))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Analyzer;
+
+    fn fix(files: &[(&str, &str)], strategy: CanonicalStrategy) -> BTreeMap<String, String> {
+        let mut analyzer = Analyzer::new();
+        for (filename, code) in files {
+            analyzer.add_source(filename, code);
+        }
+        let sources: HashMap<&str, &str> = files.iter().copied().collect();
+        let imports = analyzer.import_graph();
+        let comparer = DeepComparer::new(analyzer.declarations(), Some(&imports));
+        let consolidations = skip_global_scripts(
+            plan_consolidations(&analyzer.findings(), strategy, None, &comparer),
+            |filename| sources.get(filename).copied(),
+        );
+        let edits = consolidation_edits(&consolidations, &ModuleResolver::new(), |filename| {
+            sources.get(filename).copied()
+        });
+        edits
+            .into_iter()
+            .map(|(filename, edits)| {
+                let fixed = apply_edits(sources[filename.as_str()], &edits).unwrap();
+                (filename, fixed)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_consolidates_into_most_used_copy() {
+        let fixed = fix(
+            &[
+                ("src/a.ts", "export interface IFoo { x: string }\n"),
+                (
+                    "src/b.ts",
+                    "import { Bar } from './bar';\ninterface IFoo { x: string }\nconst f: IFoo = { x: '' };\n",
+                ),
+                (
+                    "src/use.ts",
+                    "import { IFoo } from './a';\nconst f: IFoo = { x: '' };\nconst g: IFoo = f;\n",
+                ),
+            ],
+            CanonicalStrategy::MostUsed,
+        );
+
+        assert_eq!(fixed.len(), 1);
+        assert_eq!(
+            fixed["src/b.ts"],
+            "import { Bar } from './bar';\nimport type { IFoo } from './a';\nconst f: IFoo = { x: '' };\n"
+        );
+    }
+
+    #[test]
+    fn test_exports_canonical_and_reexports_removed_copy() {
+        let fixed = fix(
+            &[
+                (
+                    "lib/a.ts",
+                    "interface IFoo { x: string }\nconst f: IFoo = { x: '' };\n",
+                ),
+                ("lib/b.ts", "export interface IFoo { x: string }\n"),
+            ],
+            CanonicalStrategy::MostUsed,
+        );
+
+        assert_eq!(
+            fixed["lib/a.ts"],
+            "export interface IFoo { x: string }\nconst f: IFoo = { x: '' };\n"
+        );
+        assert_eq!(fixed["lib/b.ts"], "export type { IFoo } from './a';\n");
+    }
+
+    #[test]
+    fn test_exported_strategy_prefers_exported_copy() {
+        let fixed = fix(
+            &[
+                ("a.ts", "interface IFoo { x: string }\n"),
+                ("b.ts", "export interface IFoo { x: string }\n"),
+            ],
+            CanonicalStrategy::Exported,
+        );
+
+        assert_eq!(fixed.len(), 1);
+        assert_eq!(fixed["a.ts"], "");
+    }
+
+    #[test]
+    fn test_apply_edits_rejects_stale_ranges() {
+        let edit = |start, end| TextEdit {
+            start,
+            end,
+            text: String::new(),
+        };
+        let source = "type Café = 1;\n";
+
+        assert!(apply_edits(source, &[edit(10, 40)]).is_err());
+        assert!(apply_edits(source, &[edit(8, 9)]).is_err());
+        assert!(apply_edits(source, &[edit(5, 3)]).is_err());
+        assert_eq!(
+            apply_edits(source, &[edit(0, source.len())]),
+            Ok(String::new())
+        );
+    }
+
+    #[test]
+    fn test_apply_edits_rejects_overlaps() {
+        let edit = |start, end, text: &str| TextEdit {
+            start,
+            end,
+            text: text.to_string(),
+        };
+        let source = "type A = 1;\ntype B = 2;\n";
+
+        assert_eq!(
+            apply_edits(
+                source,
+                &[
+                    edit(12, 24, ""),
+                    edit(0, 0, "export "),
+                    edit(0, 0, "// a\n")
+                ]
+            ),
+            Ok("export // a\ntype A = 1;\n".to_string())
+        );
+        assert_eq!(
+            apply_edits(source, &[edit(0, 12, ""), edit(5, 6, "Z")]),
+            Err("overlapping edits at 0..12 and 5..6".to_string())
+        );
+    }
+
+    #[test]
+    fn test_leaves_global_declaration_files_alone() {
+        let fixed = fix(
+            &[
+                (
+                    "a.ts",
+                    "export interface IFoo { x: string }\nconst f: IFoo = { x: '' };\n",
+                ),
+                ("global.d.ts", "interface IFoo { x: string }\n"),
+                ("module.d.ts", "export {};\ninterface IFoo { x: string }\n"),
+            ],
+            CanonicalStrategy::MostUsed,
+        );
+
+        assert_eq!(fixed.len(), 1);
+        assert_eq!(fixed["module.d.ts"], "export {};\n");
+    }
+
+    #[test]
+    fn test_skips_identical_text_referring_to_different_types() {
+        let fixed = fix(
+            &[
+                ("a/user.ts", "export interface Profile { id: string }"),
+                ("b/user.ts", "export interface Profile { id: number }"),
+                (
+                    "a/order.ts",
+                    "import { Profile } from './user';\ninterface Order { user: Profile }",
+                ),
+                (
+                    "b/order.ts",
+                    "import { Profile } from './user';\ninterface Order { user: Profile }",
+                ),
+            ],
+            CanonicalStrategy::MostUsed,
+        );
+
+        assert!(fixed.is_empty());
+    }
+
+    #[test]
+    fn test_removes_imports_only_the_removed_copy_used() {
+        let fixed = fix(
+            &[
+                (
+                    "a/user.ts",
+                    "export interface Profile { id: string }\nexport type Tag = string;\n",
+                ),
+                (
+                    "a/order.ts",
+                    "import { Profile } from './user';\nexport interface Order { user: Profile }\n",
+                ),
+                (
+                    "b/order.ts",
+                    "import { Profile } from '../a/user';\nexport interface Order { user: Profile }\n",
+                ),
+                (
+                    "c/order.ts",
+                    "import { Profile, Tag } from '../a/user';\nexport interface Order { user: Profile }\ntype Owner = Tag;\n",
+                ),
+            ],
+            CanonicalStrategy::MostUsed,
+        );
+
+        assert_eq!(
+            fixed["b/order.ts"],
+            "export type { Order } from '../a/order';\n"
+        );
+        assert_eq!(
+            fixed["c/order.ts"],
+            "import { Tag } from '../a/user';\nexport type { Order } from '../a/order';\ntype Owner = Tag;\n"
+        );
+    }
+}
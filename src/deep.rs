@@ -0,0 +1,301 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::analyzer::{Finding, Severity};
+use crate::imports::{resolve_declaration, ImportGraph};
+use crate::shared_lib::FoundDeclarationNode;
+
+/// Two nested declarations that turned out to be equivalent while comparing a
+/// duplicate pair, e.g. `UserA` in `a.ts` and `UserB` in `b.ts`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Equivalence {
+    pub first: String,
+    pub first_filename: String,
+    pub second: String,
+    pub second_filename: String,
+}
+
+/// Identifies one declaration, for cycle detection.
+type DeclarationKey<'a> = (&'a str, usize);
+
+/// Compares declaration bodies structurally, expanding references to other
+/// known declarations instead of comparing them by name.
+pub struct DeepComparer<'a> {
+    declarations: &'a HashMap<String, Vec<FoundDeclarationNode>>,
+    imports: Option<&'a ImportGraph>,
+}
+
+impl<'a> DeepComparer<'a> {
+    /// `imports` is used to tell apart same-named declarations; without it a
+    /// reference only resolves when it is declared in the same file or its
+    /// name is unique.
+    pub fn new(
+        declarations: &'a HashMap<String, Vec<FoundDeclarationNode>>,
+        imports: Option<&'a ImportGraph>,
+    ) -> Self {
+        DeepComparer {
+            declarations,
+            imports,
+        }
+    }
+
+    /// The declaration `name` refers to when used in `filename`.
+    pub fn resolve(&self, filename: &str, name: &str) -> Option<&'a FoundDeclarationNode> {
+        resolve_declaration(self.declarations, self.imports, filename, name)
+    }
+
+    /// Whether `a` and `b` are structurally identical once every nested
+    /// reference is expanded. On success returns the nested declarations that
+    /// had to be matched up along the way (empty when the bodies are equal
+    /// outright).
+    pub fn equivalent(
+        &self,
+        a: &FoundDeclarationNode,
+        b: &FoundDeclarationNode,
+    ) -> Option<Vec<Equivalence>> {
+        let mut assumed = HashSet::new();
+        let mut chain = Vec::new();
+        self.compare(a, b, &mut assumed, &mut chain)
+            .then_some(chain)
+    }
+
+    fn compare<'n>(
+        &self,
+        a: &'n FoundDeclarationNode,
+        b: &'n FoundDeclarationNode,
+        assumed: &mut HashSet<(DeclarationKey<'n>, DeclarationKey<'n>)>,
+        chain: &mut Vec<Equivalence>,
+    ) -> bool
+    where
+        'a: 'n,
+    {
+        let key_a = (a.filename.as_str(), a.span_start);
+        let key_b = (b.filename.as_str(), b.span_start);
+        // Recursive types: a pair already being compared is assumed equal, and
+        // the comparison further up decides.
+        if key_a == key_b || !assumed.insert((key_a, key_b)) {
+            return true;
+        }
+
+        let tokens_a = tokens(&a.body);
+        let tokens_b = tokens(&b.body);
+        if tokens_a.len() != tokens_b.len() {
+            return false;
+        }
+
+        for (token_a, token_b) in tokens_a.into_iter().zip(tokens_b) {
+            let is_reference_a = a.references.iter().any(|r| r == token_a);
+            let is_reference_b = b.references.iter().any(|r| r == token_b);
+            if !is_reference_a || !is_reference_b {
+                if token_a != token_b {
+                    return false;
+                }
+                continue;
+            }
+
+            match (
+                self.resolve(&a.filename, token_a),
+                self.resolve(&b.filename, token_b),
+            ) {
+                (Some(nested_a), Some(nested_b)) => {
+                    let same = nested_a.filename == nested_b.filename
+                        && nested_a.span_start == nested_b.span_start;
+                    if same {
+                        continue;
+                    }
+                    if !self.compare(nested_a, nested_b, assumed, chain) {
+                        return false;
+                    }
+                    let equivalence = Equivalence {
+                        first: nested_a.name.clone(),
+                        first_filename: nested_a.filename.clone(),
+                        second: nested_b.name.clone(),
+                        second_filename: nested_b.filename.clone(),
+                    };
+                    if !chain.contains(&equivalence) {
+                        chain.push(equivalence);
+                    }
+                }
+                // Unknown (external, global or ambiguous) types only match by name.
+                _ if token_a == token_b => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Re-judges every finding by deep comparison: pairs whose bodies only differ
+/// in equivalent nested types become critical, and pairs with identical text
+/// that refers to different, non-equivalent types become warnings. Documented
+/// copies keep their severity, since they are to be updated, not merged.
+pub fn apply_deep_comparison(findings: &mut [Finding], comparer: &DeepComparer) {
+    for finding in findings {
+        if finding.documented_copy().is_some() {
+            continue;
+        }
+        match comparer.equivalent(&finding.first, &finding.second) {
+            Some(chain) => {
+                finding.severity = Severity::Critical;
+                finding.equivalences = chain;
+            }
+            None => finding.severity = Severity::Warning,
+        }
+    }
+}
+
+/// Splits a serialized body into identifiers, quoted literals and single
+/// punctuation characters, dropping whitespace.
+fn tokens(body: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut chars = body.char_indices().peekable();
+
+    while let Some((start, ch)) = chars.next() {
+        if ch.is_whitespace() {
+            continue;
+        }
+        let mut end = start + ch.len_utf8();
+        if ch == '"' || ch == '`' {
+            let mut escaped = false;
+            for (i, next) in chars.by_ref() {
+                end = i + next.len_utf8();
+                if escaped {
+                    escaped = false;
+                } else if next == '\\' {
+                    escaped = true;
+                } else if next == ch {
+                    break;
+                }
+            }
+        } else if is_identifier_char(ch) {
+            while let Some(&(i, next)) = chars.peek() {
+                if !is_identifier_char(next) {
+                    break;
+                }
+                end = i + next.len_utf8();
+                chars.next();
+            }
+        }
+        tokens.push(&body[start..end]);
+    }
+    tokens
+}
+
+pub(crate) fn is_identifier_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_' || ch == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Analyzer;
+
+    fn deep_findings(analyzer: &Analyzer) -> Vec<Finding> {
+        let graph = analyzer.import_graph();
+        let comparer = DeepComparer::new(analyzer.declarations(), Some(&graph));
+        let mut findings = analyzer.findings();
+        apply_deep_comparison(&mut findings, &comparer);
+        findings
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(
+            tokens(r#"{ user: UserA; tag: "a b" }"#),
+            vec!["{", "user", ":", "UserA", ";", "tag", ":", "\"a b\"", "}"]
+        );
+    }
+
+    #[test]
+    fn test_equivalent_nested_types_upgrade_to_critical() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source(
+            "a.ts",
+            "interface UserA { id: string }\ninterface Order { user: UserA }",
+        );
+        analyzer.add_source(
+            "b.ts",
+            "interface UserB { id: string }\ninterface Order { user: UserB }",
+        );
+
+        assert_eq!(analyzer.findings()[0].severity, Severity::Warning);
+
+        let findings = deep_findings(&analyzer);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert_eq!(
+            findings[0].equivalences,
+            vec![Equivalence {
+                first: "UserA".to_string(),
+                first_filename: "a.ts".to_string(),
+                second: "UserB".to_string(),
+                second_filename: "b.ts".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_identical_text_with_different_targets_downgrades_to_warning() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a/user.ts", "export interface Profile { id: string }");
+        analyzer.add_source("b/user.ts", "export interface Profile { id: number }");
+        analyzer.add_source(
+            "a/order.ts",
+            "import { Profile } from './user';\ninterface Order { user: Profile }",
+        );
+        analyzer.add_source(
+            "b/order.ts",
+            "import { Profile } from './user';\ninterface Order { user: Profile }",
+        );
+
+        let findings = deep_findings(&analyzer);
+        let order = findings.iter().find(|f| f.name == "Order").unwrap();
+        assert_eq!(order.severity, Severity::Warning);
+        assert!(order.equivalences.is_empty());
+        assert_eq!(
+            order.message(),
+            "Duplicate interface 'Order' with identical text referring to different types"
+        );
+    }
+
+    #[test]
+    fn test_documented_copies_keep_their_severity() {
+        let dir = crate::test_support::temp_tree(&[
+            (
+                "docs/order.md",
+                "```ts\ninterface UserDoc { id: string }\ninterface Order { user: UserDoc }\n```\n",
+            ),
+            (
+                "src/order.ts",
+                "interface User { id: string }\ninterface Order { user: User }",
+            ),
+        ]);
+        let mut analyzer = Analyzer::new().docs(true);
+        assert!(analyzer.add_path(dir.path()).is_empty());
+
+        let findings = deep_findings(&analyzer);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert!(findings[0].documented_copy().is_some());
+        assert!(findings[0]
+            .message()
+            .starts_with("Documented interface 'Order'"));
+    }
+
+    #[test]
+    fn test_recursive_types_terminate() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source(
+            "a.ts",
+            "interface NodeA { next: NodeA | null }\ntype List = { head: NodeA }",
+        );
+        analyzer.add_source(
+            "b.ts",
+            "interface NodeB { next: NodeB | null }\ntype List = { head: NodeB }",
+        );
+
+        let findings = deep_findings(&analyzer);
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert_eq!(findings[0].equivalences.len(), 1);
+    }
+}
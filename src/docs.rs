@@ -0,0 +1,129 @@
+use oxc::span::SourceType;
+use std::ops::Range;
+
+use crate::component::blank;
+
+/// A fenced `ts`, `typescript` or `tsx` code block in Markdown or MDX.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeFence {
+    /// Byte offsets of the block contents, without the fence lines.
+    pub range: Range<usize>,
+    pub tsx: bool,
+}
+
+/// The TypeScript code fences of a Markdown document, in source order. An
+/// unclosed fence runs to the end of the document.
+pub fn code_fences(code: &str) -> Vec<CodeFence> {
+    let mut fences = Vec::new();
+    // (fence char, fence length, content start, language)
+    let mut open: Option<(char, usize, usize, Option<bool>)> = None;
+    let mut offset = 0;
+
+    for line in code.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+
+        let trimmed = line.trim_start_matches(' ');
+        if line.len() - trimmed.len() > 3 {
+            continue;
+        }
+        let Some(marker) = trimmed.chars().next().filter(|ch| *ch == '`' || *ch == '~') else {
+            continue;
+        };
+        let length = trimmed.chars().take_while(|ch| *ch == marker).count();
+        if length < 3 {
+            continue;
+        }
+        let info = trimmed[length..].trim();
+
+        match open {
+            Some((open_marker, open_length, start, tsx))
+                if marker == open_marker && length >= open_length && info.is_empty() =>
+            {
+                if let Some(tsx) = tsx {
+                    fences.push(CodeFence {
+                        range: start..line_start,
+                        tsx,
+                    });
+                }
+                open = None;
+            }
+            Some(_) => {}
+            None => {
+                let language = info
+                    .split(|ch: char| ch.is_whitespace() || ch == '{' || ch == ',')
+                    .next()
+                    .unwrap_or("");
+                let tsx = match language {
+                    "ts" | "typescript" => Some(false),
+                    "tsx" => Some(true),
+                    _ => None,
+                };
+                open = Some((marker, length, offset, tsx));
+            }
+        }
+    }
+
+    if let Some((_, _, start, Some(tsx))) = open {
+        fences.push(CodeFence {
+            range: start..code.len(),
+            tsx,
+        });
+    }
+    fences
+}
+
+/// Calls `parse` with one source per code fence: the document up to the end
+/// of that fence with everything but the fence blanked out, so offsets, lines
+/// and columns point into the document. A single buffer is reused for every
+/// fence.
+pub fn for_each_fence_source(code: &str, mut parse: impl FnMut(&str, SourceType)) {
+    let mut masked: String = blank(code).collect();
+    for fence in code_fences(code) {
+        let range = fence.range;
+        masked.replace_range(range.clone(), &code[range.clone()]);
+        let source_type = if fence.tsx {
+            SourceType::tsx()
+        } else {
+            SourceType::ts()
+        };
+        parse(&masked[..range.end], source_type);
+        let blanked: String = blank(&code[range.clone()]).collect();
+        masked.replace_range(range, &blanked);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_fences() {
+        let doc = "# Users\n\n```ts\ninterface User { id: string }\n```\n\n```js\nconst a = 1;\n```\n\n````typescript title=\"user.ts\"\ntype Id = string;\n```\nstill inside\n````\n\n~~~tsx\nconst b = <div />;\n";
+        let fences = code_fences(doc);
+
+        assert_eq!(fences.len(), 3);
+        assert_eq!(
+            &doc[fences[0].range.clone()],
+            "interface User { id: string }\n"
+        );
+        assert_eq!(
+            &doc[fences[1].range.clone()],
+            "type Id = string;\n```\nstill inside\n"
+        );
+        assert!(fences[2].tsx);
+        assert_eq!(&doc[fences[2].range.clone()], "const b = <div />;\n");
+    }
+
+    #[test]
+    fn test_fence_sources_stand_alone() {
+        let doc = "```ts\ntype A = string;\n```\n\n```ts\ntype B = \n```\n";
+        let mut sources = Vec::new();
+        for_each_fence_source(doc, |masked, _| sources.push(masked.to_string()));
+
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0], "     \ntype A = string;\n");
+        assert!(!sources[1].contains("type A") && sources[1].ends_with("\ntype B = \n"));
+        assert_eq!(sources[1].len(), doc.find("type B").unwrap() + 10);
+    }
+}
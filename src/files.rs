@@ -0,0 +1,324 @@
+use ignore::WalkBuilder;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Walker configured with the ignore rules used for every scan.
+pub fn ts_walker(path: &Path) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(path);
+    builder
+        .hidden(true)
+        .git_ignore(true)
+        .git_global(true)
+        .git_exclude(true);
+    builder
+}
+
+pub fn is_ts_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "ts" || ext == "tsx" || ext == "mts" || ext == "cts")
+}
+
+/// `.d.ts`, `.d.mts` and `.d.cts` files, whose declarations are ambient.
+pub fn is_declaration_file(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| {
+        let name = name.to_string_lossy();
+        name.ends_with(".d.ts") || name.ends_with(".d.mts") || name.ends_with(".d.cts")
+    })
+}
+
+/// Folders build tools conventionally emit declaration files into.
+const OUTPUT_DIRS: [&str; 3] = ["dist", "build", "out"];
+
+/// Whether `path` is a declaration file emitted by a build: below one of
+/// `output_dirs` (absolute, e.g. a tsconfig's `outDir`) or a folder named like
+/// build output.
+pub fn is_generated_declaration(path: &Path, output_dirs: &[PathBuf]) -> bool {
+    if !is_declaration_file(path) {
+        return false;
+    }
+    let absolute = absolute(path);
+    output_dirs.iter().any(|dir| absolute.starts_with(dir))
+        || path.components().any(|component| {
+            matches!(component, Component::Normal(name) if OUTPUT_DIRS.iter().any(|dir| name == *dir))
+        })
+}
+
+/// Single-file components whose TypeScript script blocks are scanned.
+pub fn is_component_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "vue" || ext == "svelte" || ext == "astro")
+}
+
+/// Markdown and MDX documents, scanned for TypeScript code fences when asked to.
+pub fn is_doc_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "md" || ext == "mdx")
+}
+
+/// JavaScript sources, scanned for JSDoc `@typedef`s when asked to.
+pub fn is_js_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "js" || ext == "jsx" || ext == "mjs" || ext == "cjs")
+}
+
+/// Whether `path` is scanned, given whether JavaScript is included.
+pub fn is_source_file(path: &Path, include_js: bool) -> bool {
+    is_ts_file(path) || is_component_file(path) || (include_js && is_js_file(path))
+}
+
+pub fn find_ts_files(path: &Path) -> Vec<String> {
+    find_source_files(path, false)
+}
+
+/// Like [`find_ts_files`], optionally including JavaScript files.
+pub fn find_source_files(path: &Path, include_js: bool) -> Vec<String> {
+    let mut files = Vec::new();
+
+    for entry in ts_walker(path).build().filter_map(Result::ok) {
+        if is_source_file(entry.path(), include_js) {
+            files.push(normalize(entry.path()).to_string_lossy().to_string());
+        }
+    }
+
+    files
+}
+
+/// Every Markdown and MDX document below `path`.
+pub fn find_doc_files(path: &Path) -> Vec<String> {
+    ts_walker(path)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| is_doc_file(entry.path()))
+        .map(|entry| normalize(entry.path()).to_string_lossy().to_string())
+        .collect()
+}
+
+/// How a source file is stored on disk. UTF-8 keeps its byte order mark in the
+/// decoded text; UTF-16 is only recognized by one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    /// The encoding [`decode_source`] reads `bytes` as.
+    pub fn detect(bytes: &[u8]) -> Encoding {
+        if bytes.starts_with(&[0xFF, 0xFE]) {
+            Encoding::Utf16Le
+        } else if bytes.starts_with(&[0xFE, 0xFF]) {
+            Encoding::Utf16Be
+        } else {
+            Encoding::Utf8
+        }
+    }
+
+    /// `text` encoded back into this encoding, byte order mark included.
+    pub fn encode(self, text: &str) -> Vec<u8> {
+        let units = |unit: fn(u16) -> [u8; 2], bom: [u8; 2]| {
+            bom.into_iter()
+                .chain(text.encode_utf16().flat_map(unit))
+                .collect()
+        };
+        match self {
+            Encoding::Utf8 => text.as_bytes().to_vec(),
+            Encoding::Utf16Le => units(u16::to_le_bytes, [0xFF, 0xFE]),
+            Encoding::Utf16Be => units(u16::to_be_bytes, [0xFE, 0xFF]),
+        }
+    }
+}
+
+/// Reads a source file. UTF-16 files with a byte order mark are decoded; a
+/// broken symlink or invalid UTF-8 comes back as an error saying so.
+pub fn read_source(path: &Path) -> io::Result<String> {
+    read_encoded_source(path).map(|(code, _)| code)
+}
+
+/// Like [`read_source`], also telling how the file was encoded so it can be
+/// written back the same way.
+pub fn read_encoded_source(path: &Path) -> io::Result<(String, Encoding)> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok((decode_source(&bytes)?, Encoding::detect(&bytes))),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => match std::fs::read_link(path) {
+            Ok(target) => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("broken symlink to {}", target.display()),
+            )),
+            Err(_) => Err(err),
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// Decodes UTF-8 (a byte order mark is kept, the parser skips it) or UTF-16
+/// with a byte order mark.
+pub fn decode_source(bytes: &[u8]) -> io::Result<String> {
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return decode_utf16(rest, u16::from_le_bytes);
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        return decode_utf16(rest, u16::from_be_bytes);
+    }
+    if bytes.contains(&0) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "contains NUL bytes (UTF-16 without a byte order mark, or binary)",
+        ));
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(code) => Ok(code.to_string()),
+        Err(err) => {
+            let valid = String::from_utf8_lossy(&bytes[..err.valid_up_to()]);
+            let line = valid.matches('\n').count() + 1;
+            let col = valid
+                .rsplit('\n')
+                .next()
+                .map_or(0, |last| last.chars().count())
+                + 1;
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid UTF-8 at line {}, column {}", line, col),
+            ))
+        }
+    }
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> io::Result<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "truncated UTF-16 (odd number of bytes)",
+        ));
+    }
+    let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid UTF-16: {}", err),
+            )
+        })
+}
+
+/// Lexically cleans a path: drops `.` segments and folds `..` where possible.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                let ends_in_normal =
+                    matches!(out.components().next_back(), Some(Component::Normal(_)));
+                if ends_in_normal {
+                    out.pop();
+                } else {
+                    out.push("..");
+                }
+            }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+/// `path` made absolute against the working directory, without touching the
+/// file system.
+pub fn absolute(path: &Path) -> PathBuf {
+    match std::env::current_dir() {
+        Ok(cwd) => normalize(&cwd.join(path)),
+        Err(_) => normalize(path),
+    }
+}
+
+/// `path` relative to the working directory when it lies below it, matching
+/// the shape of the paths `find_ts_files` returns.
+pub fn relative_to_cwd(path: &Path) -> PathBuf {
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize(Path::new("./a/../b/./c")), PathBuf::from("b/c"));
+        assert_eq!(normalize(Path::new("../a")), PathBuf::from("../a"));
+        assert_eq!(normalize(Path::new(".")), PathBuf::new());
+    }
+
+    #[test]
+    fn test_declaration_files() {
+        assert!(is_ts_file(Path::new("a.mts")));
+        assert!(is_ts_file(Path::new("a.cts")));
+        assert!(is_declaration_file(Path::new("types/a.d.mts")));
+        assert!(!is_declaration_file(Path::new("src/a.ts")));
+
+        let out_dir = [absolute(Path::new("generated"))];
+        assert!(is_generated_declaration(Path::new("dist/a.d.ts"), &[]));
+        assert!(is_generated_declaration(
+            Path::new("generated/a.d.ts"),
+            &out_dir
+        ));
+        assert!(!is_generated_declaration(
+            Path::new("types/a.d.ts"),
+            &out_dir
+        ));
+        assert!(!is_generated_declaration(Path::new("dist/a.ts"), &[]));
+    }
+
+    #[test]
+    fn test_find_ts_files_normalizes_paths() {
+        let files = find_ts_files(Path::new("./ts"));
+        assert!(files.contains(&"ts/example.ts".to_string()));
+    }
+
+    #[test]
+    fn test_decode_source() {
+        assert_eq!(decode_source(b"type A = 1;").unwrap(), "type A = 1;");
+        assert_eq!(
+            decode_source(b"\xEF\xBB\xBFtype A = 1;").unwrap(),
+            "\u{FEFF}type A = 1;"
+        );
+        assert_eq!(
+            decode_source(&[0xFF, 0xFE, b'a', 0, b'\n', 0]).unwrap(),
+            "a\n"
+        );
+        assert_eq!(decode_source(&[0xFE, 0xFF, 0, b'a']).unwrap(), "a");
+
+        let err = decode_source(b"a\nb\xFFc").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "invalid UTF-8 at line 2, column 2");
+        assert!(decode_source(&[b'a', 0, b'b', 0]).is_err());
+    }
+
+    #[test]
+    fn test_encoding_round_trip() {
+        for bytes in [
+            &b"\xEF\xBB\xBFtype A = '\xC3\xA9';"[..],
+            &[0xFF, 0xFE, b'a', 0, 0xE9, 0][..],
+            &[0xFE, 0xFF, 0, b'a', 0, 0xE9][..],
+        ] {
+            let encoding = Encoding::detect(bytes);
+            assert_eq!(encoding.encode(&decode_source(bytes).unwrap()), bytes);
+        }
+        assert_eq!(Encoding::detect(&[0xFF, 0xFE]), Encoding::Utf16Le);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_source_broken_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let link = dir.path().join("gone.ts");
+        std::os::unix::fs::symlink(dir.path().join("missing.ts"), &link).unwrap();
+
+        let err = read_source(&link).unwrap_err();
+        assert!(err.to_string().starts_with("broken symlink to "));
+    }
+}
@@ -0,0 +1,313 @@
+use similar::TextDiff;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use tta::consolidate::{
+    apply_edits, consolidation_edits, plan_consolidations, skip_global_scripts, CanonicalStrategy,
+    TextEdit,
+};
+use tta::deep::DeepComparer;
+use tta::files::{find_source_files, read_encoded_source, Encoding};
+use tta::imports::{ImportGraph, ModuleResolver};
+use tta::rename::{check_new_name, propose_renames, rename_edits, PrefixRule, RenameProposal};
+use tta::usage::Usage;
+use tta::workspace::Workspace;
+use tta::Analyzer;
+
+use crate::module_resolver;
+
+#[derive(clap::Args)]
+pub struct FixArgs {
+    /// Path to .ts(x) file or directory
+    path: Option<String>,
+
+    /// Which copy to keep: most-used, exported or shared-lib
+    #[clap(long, value_name = "STRATEGY", default_value = "most-used")]
+    canonical: CanonicalStrategy,
+
+    #[clap(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(clap::Args)]
+pub struct RenameArgs {
+    /// Path to .ts(x) file or directory
+    path: Option<String>,
+
+    /// What new names are prefixed with: folder or project
+    #[clap(long, value_name = "RULE", default_value = "folder")]
+    prefix: PrefixRule,
+
+    /// Confirm or override every proposed name on stdin
+    #[clap(short, long)]
+    interactive: bool,
+
+    #[clap(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(clap::Args)]
+struct OutputArgs {
+    /// Write the changes to disk instead of printing them as a unified diff
+    #[clap(long)]
+    write: bool,
+}
+
+/// Everything scanned below a target path.
+struct Scan {
+    analyzer: Analyzer,
+    sources: HashMap<String, String>,
+    encodings: HashMap<String, Encoding>,
+    resolver: ModuleResolver,
+    workspace: Option<Workspace>,
+}
+
+fn scan(path: &Option<String>, include_js: bool) -> Scan {
+    let target_path = path.clone().unwrap_or_else(|| ".".to_string());
+    let resolver = module_resolver(&target_path, &[]);
+
+    let mut analyzer = Analyzer::new().resolver(resolver.clone());
+    let mut sources = HashMap::new();
+    let mut encodings = HashMap::new();
+    for path in find_source_files(Path::new(&target_path), include_js) {
+        let (code, encoding) = match read_encoded_source(Path::new(&path)) {
+            Ok(read) => read,
+            Err(err) => {
+                eprintln!("Skipped {}: {}", path, err);
+                continue;
+            }
+        };
+        analyzer.add_source(&path, &code);
+        encodings.insert(path.clone(), encoding);
+        sources.insert(path, code);
+    }
+
+    Scan {
+        analyzer,
+        sources,
+        encodings,
+        resolver,
+        workspace: Workspace::discover(Path::new(&target_path)),
+    }
+}
+
+/// Consolidates every set of identical duplicates below the target path,
+/// printing the changes as a unified diff unless `--write` is given.
+pub fn run(args: &FixArgs, include_js: bool) {
+    let scan = scan(&args.path, include_js);
+    let imports = scan.analyzer.import_graph();
+    let comparer = DeepComparer::new(scan.analyzer.declarations(), Some(&imports));
+    let consolidations = skip_global_scripts(
+        plan_consolidations(
+            &scan.analyzer.findings(),
+            args.canonical,
+            scan.workspace.as_ref(),
+            &comparer,
+        ),
+        |filename| scan.sources.get(filename).map(String::as_str),
+    );
+    if consolidations.is_empty() {
+        eprintln!("No identical duplicates to consolidate.");
+        return;
+    }
+
+    for consolidation in &consolidations {
+        eprintln!(
+            "Keeping '{}' in {}:{}, removing {} cop{}.",
+            consolidation.name,
+            consolidation.canonical.filename,
+            consolidation.canonical.line,
+            consolidation.removed.len(),
+            if consolidation.removed.len() == 1 {
+                "y"
+            } else {
+                "ies"
+            }
+        );
+    }
+
+    let edits = consolidation_edits(&consolidations, &scan.resolver, |filename| {
+        scan.sources.get(filename).map(String::as_str)
+    });
+    emit(&edits, &scan, &args.output);
+}
+
+/// Gives every declaration in a "same name, different body" collision a
+/// distinct name, updating its usages and the imports naming it.
+pub fn run_rename(args: &RenameArgs, include_js: bool) {
+    let scan = scan(&args.path, include_js);
+    let imports = scan.analyzer.import_graph();
+    let (mut proposals, unnamed) = propose_renames(
+        &scan.analyzer.findings(),
+        scan.analyzer.declarations(),
+        &imports,
+        args.prefix,
+        scan.workspace.as_ref(),
+    );
+    for message in &unnamed {
+        eprintln!("{}", message);
+    }
+    if proposals.is_empty() {
+        if unnamed.is_empty() {
+            eprintln!("No name collisions to rename.");
+        }
+        return;
+    }
+
+    if args.interactive {
+        proposals = confirm(proposals, &scan.analyzer, &imports);
+    }
+    for proposal in &proposals {
+        eprintln!(
+            "Renaming '{}' in {}:{} to '{}'.",
+            proposal.declaration.name,
+            proposal.declaration.filename,
+            proposal.declaration.line,
+            proposal.new_name
+        );
+    }
+
+    let usages: Vec<Usage> = scan.analyzer.index().usages().cloned().collect();
+    let edits = rename_edits(
+        &proposals,
+        scan.analyzer.declarations(),
+        &usages,
+        &imports,
+        |filename| scan.sources.get(filename).map(String::as_str),
+    );
+    emit(&edits, &scan, &args.output);
+}
+
+/// Asks for every proposal: Enter accepts it, `-` skips the declaration and
+/// anything else is taken as the new name. Names that aren't valid or clash
+/// with a declaration are refused and asked for again.
+fn confirm(
+    proposals: Vec<RenameProposal>,
+    analyzer: &Analyzer,
+    imports: &ImportGraph,
+) -> Vec<RenameProposal> {
+    let mut lines = io::stdin().lock().lines();
+    let mut accepted: Vec<RenameProposal> = Vec::new();
+    for (i, proposal) in proposals.iter().enumerate() {
+        loop {
+            eprint!(
+                "Rename '{}' in {}:{} to [{}]: ",
+                proposal.declaration.name,
+                proposal.declaration.filename,
+                proposal.declaration.line,
+                proposal.new_name
+            );
+            io::stderr().flush().ok();
+
+            let answer = match lines.next() {
+                Some(Ok(line)) => line.trim().to_string(),
+                _ => String::new(),
+            };
+            match answer.as_str() {
+                "" => accepted.push(proposal.clone()),
+                "-" => {}
+                name => {
+                    let others: Vec<RenameProposal> = accepted
+                        .iter()
+                        .chain(&proposals[i + 1..])
+                        .cloned()
+                        .collect();
+                    let checked = check_new_name(
+                        &proposal.declaration,
+                        name,
+                        analyzer.declarations(),
+                        imports,
+                        &others,
+                    );
+                    if let Err(err) = checked {
+                        eprintln!("{}", err);
+                        continue;
+                    }
+                    accepted.push(RenameProposal {
+                        new_name: name.to_string(),
+                        ..proposal.clone()
+                    });
+                }
+            }
+            break;
+        }
+    }
+    accepted
+}
+
+/// Prints `edits` as a unified diff, or writes them with `--write` in the
+/// encoding each file was read in. Every file is written to a temporary sibling
+/// first and only renamed over the original once all of them were written, so
+/// a failed rewrite or write leaves the tree untouched.
+fn emit(edits: &BTreeMap<String, Vec<TextEdit>>, scan: &Scan, output: &OutputArgs) {
+    let mut fixed_files = Vec::with_capacity(edits.len());
+    for (filename, edits) in edits {
+        match apply_edits(&scan.sources[filename], edits) {
+            Ok(fixed) => fixed_files.push((filename, fixed)),
+            Err(err) => {
+                eprintln!("Failed to rewrite {}: {}", filename, err);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if output.write {
+        write_all(&fixed_files, scan);
+    } else {
+        for (filename, fixed) in &fixed_files {
+            let original = &scan.sources[*filename];
+            let diff = TextDiff::from_lines(original.as_str(), fixed.as_str());
+            print!(
+                "{}",
+                diff.unified_diff()
+                    .header(&format!("a/{}", filename), &format!("b/{}", filename))
+            );
+        }
+    }
+
+    eprintln!(
+        "\n{} {} file{}.",
+        if output.write {
+            "Rewrote"
+        } else {
+            "Would rewrite"
+        },
+        edits.len(),
+        if edits.len() == 1 { "" } else { "s" }
+    );
+}
+
+/// Writes every fixed file next to its original, keeping its permissions, then
+/// renames them into place. Exits without touching any original if one of the
+/// writes fails.
+fn write_all(fixed_files: &[(&String, String)], scan: &Scan) {
+    let mut written: Vec<(String, &String)> = Vec::with_capacity(fixed_files.len());
+    let discard = |written: &[(String, &String)]| {
+        for (temporary, _) in written {
+            let _ = std::fs::remove_file(temporary);
+        }
+    };
+
+    for (filename, fixed) in fixed_files {
+        let temporary = format!("{}.tta-tmp", filename);
+        let encoding = scan.encodings[*filename];
+        let result = std::fs::write(&temporary, encoding.encode(fixed)).and_then(|()| {
+            let permissions = std::fs::metadata(filename)?.permissions();
+            std::fs::set_permissions(&temporary, permissions)
+        });
+        written.push((temporary, *filename));
+        if let Err(err) = result {
+            eprintln!("Failed to write {}: {}", filename, err);
+            discard(&written);
+            std::process::exit(1);
+        }
+    }
+
+    for (i, (temporary, filename)) in written.iter().enumerate() {
+        if let Err(err) = std::fs::rename(temporary, filename) {
+            eprintln!("Failed to replace {}: {}", filename, err);
+            discard(&written[i..]);
+            std::process::exit(1);
+        }
+    }
+}
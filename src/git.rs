@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::analyzer::Finding;
+
+/// Which changes count when narrowing a report to the files a change touches.
+#[derive(Debug, Clone)]
+pub enum ChangeSet {
+    /// Everything that differs from the merge base with this revision, including
+    /// uncommitted and untracked files.
+    Since(String),
+    /// Only what is staged in the index.
+    Staged,
+}
+
+/// Returns the canonical paths of every file changed according to `changes`,
+/// asking the `git` repository that contains `dir`.
+pub fn changed_files(dir: &Path, changes: &ChangeSet) -> io::Result<HashSet<PathBuf>> {
+    let toplevel = PathBuf::from(text(&git(dir, &["rev-parse", "--show-toplevel"])?).trim());
+
+    // `-z` keeps git from quoting paths with special or non-ASCII characters.
+    let mut names = Vec::new();
+    match changes {
+        ChangeSet::Since(rev) => {
+            let base = text(&git(dir, &["merge-base", rev, "HEAD"])?);
+            names.extend(paths(&git(
+                dir,
+                &["diff", "-z", "--name-only", base.trim()],
+            )?));
+            names.extend(paths(&git(
+                dir,
+                &[
+                    "ls-files",
+                    "-z",
+                    "--others",
+                    "--exclude-standard",
+                    "--full-name",
+                ],
+            )?));
+        }
+        ChangeSet::Staged => {
+            names.extend(paths(&git(
+                dir,
+                &["diff", "-z", "--name-only", "--cached"],
+            )?));
+        }
+    }
+
+    Ok(names
+        .into_iter()
+        .map(|name| toplevel.join(name))
+        .map(|path| std::fs::canonicalize(&path).unwrap_or(path))
+        .collect())
+}
+
+/// Keeps only the findings whose name has at least one occurrence in a changed
+/// file, so a duplicate group is reported in full or not at all.
+pub fn retain_changed_groups(findings: &mut Vec<Finding>, changed: &HashSet<PathBuf>) {
+    let is_changed = |filename: &str| {
+        std::fs::canonicalize(filename)
+            .map(|path| changed.contains(&path))
+            .unwrap_or(false)
+    };
+
+    let touched: HashSet<String> = findings
+        .iter()
+        .filter(|finding| {
+            is_changed(&finding.first.filename) || is_changed(&finding.second.filename)
+        })
+        .map(|finding| finding.name.clone())
+        .collect();
+
+    findings.retain(|finding| touched.contains(&finding.name));
+}
+
+fn git(dir: &Path, args: &[&str]) -> io::Result<Vec<u8>> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+fn text(output: &[u8]) -> String {
+    String::from_utf8_lossy(output).into_owned()
+}
+
+/// The NUL-separated paths of a `-z` listing, taken byte for byte where the
+/// platform allows.
+fn paths(output: &[u8]) -> Vec<PathBuf> {
+    output
+        .split(|byte| *byte == 0)
+        .filter(|name| !name.is_empty())
+        .map(path_from_bytes)
+        .collect()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Analyzer;
+
+    #[test]
+    fn test_retain_changed_groups_keeps_whole_group() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.add_path("ts").is_empty());
+        let mut findings = analyzer.findings();
+
+        let changed = HashSet::from([std::fs::canonicalize("ts/example5.ts").unwrap()]);
+        retain_changed_groups(&mut findings, &changed);
+
+        assert!(!findings.is_empty());
+        assert!(findings.iter().all(|f| f.name == "MyInterface2"));
+    }
+
+    #[test]
+    fn test_paths_split_on_nul() {
+        assert_eq!(
+            paths("src/café.ts\0src/with space.ts\0".as_bytes()),
+            vec![
+                PathBuf::from("src/café.ts"),
+                PathBuf::from("src/with space.ts")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retain_changed_groups_drops_untouched() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.add_path("ts").is_empty());
+        let mut findings = analyzer.findings();
+
+        retain_changed_groups(&mut findings, &HashSet::new());
+        assert!(findings.is_empty());
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use tta::{Finding, FoundDeclarationNode, Severity};
+
+use crate::report::{body_diff, group_findings, severity_counts, FileCache, ScanIssues};
+
+/// A self-contained page with a sortable, filterable table of duplicate
+/// groups and, per group, the source of every pair side by side, followed by
+/// any parse errors and skipped files. Needs no network access: styles and
+/// script are inlined.
+pub fn render_html(
+    findings: &[Finding],
+    source_cache: &FileCache,
+    issues: ScanIssues,
+    ignore_warnings: bool,
+    project_of: &dyn Fn(&str) -> Option<String>,
+) -> String {
+    let groups = group_findings(findings, ignore_warnings);
+
+    let mut all_projects = BTreeSet::new();
+    let mut rows = String::new();
+    for group in &groups {
+        let copies = group.copies();
+        let files: BTreeSet<&str> = copies.iter().map(|node| node.filename.as_str()).collect();
+        let projects: BTreeSet<String> = copies
+            .iter()
+            .filter_map(|node| project_of(&node.filename))
+            .collect();
+        all_projects.extend(projects.iter().cloned());
+
+        let (severity, rank) = match group.severity() {
+            Severity::Critical => ("critical", 2),
+            Severity::Warning => ("warning", 1),
+        };
+        let kinds: BTreeSet<&str> = group
+            .findings
+            .iter()
+            .map(|finding| finding.kind.as_str())
+            .collect();
+        let kinds = kinds.into_iter().collect::<Vec<_>>().join(", ");
+        let projects = projects.into_iter().collect::<Vec<_>>().join("|");
+
+        let _ = write!(
+            rows,
+            "<tbody class=\"group\" data-severity=\"{severity}\" data-projects=\"{}\">\n\
+             <tr class=\"summary\">\
+             <td data-key=\"{rank}\"><span class=\"badge {severity}\">{severity}</span></td>\
+             <td data-key=\"{name}\"><code>{name}</code></td>\
+             <td data-key=\"{kinds}\">{kinds}</td>\
+             <td data-key=\"{}\">{}</td>\
+             <td data-key=\"{}\">{}</td>\
+             <td data-key=\"{projects_text}\">{projects_text}</td></tr>\n\
+             <tr class=\"details\" hidden><td colspan=\"6\">\n",
+            escape(&projects),
+            copies.len(),
+            copies.len(),
+            files.len(),
+            files.len(),
+            name = escape(group.name),
+            kinds = escape(&kinds),
+            projects_text = escape(&projects.replace('|', ", ")),
+        );
+        for finding in &group.findings {
+            render_pair(&mut rows, finding, source_cache, issues);
+        }
+        rows.push_str("</td></tr>\n</tbody>\n");
+    }
+
+    let (warnings, critical) = severity_counts(findings, ignore_warnings);
+    let project_options: String = all_projects
+        .iter()
+        .map(|project| format!("<option>{}</option>", escape(project)))
+        .collect();
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>tta report</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>Duplicate types</h1>\n\
+         <p class=\"counts\">{} group{}, <span class=\"badge critical\">{critical} critical</span> \
+         <span class=\"badge warning\">{warnings} warning{}</span></p>\n\
+         <div class=\"filters\">\
+         <label>Severity <select id=\"severity\"><option value=\"\">all</option>\
+         <option>critical</option><option>warning</option></select></label>\
+         {}\
+         </div>\n\
+         <table id=\"groups\">\n<thead><tr>\
+         <th>Severity</th><th>Name</th><th>Kind</th><th>Copies</th><th>Files</th><th>Projects</th>\
+         </tr></thead>\n{rows}</table>\n{}\
+         <script>{SCRIPT}</script>\n</body>\n</html>\n",
+        groups.len(),
+        if groups.len() == 1 { "" } else { "s" },
+        if warnings == 1 { "" } else { "s" },
+        if all_projects.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " <label>Project <select id=\"project\"><option value=\"\">all</option>{}</select></label>",
+                project_options
+            )
+        },
+        render_issues(issues, source_cache),
+    )
+}
+
+/// Every parse error with its line, then every skipped file.
+fn render_issues(issues: ScanIssues, source_cache: &FileCache) -> String {
+    let mut html = String::new();
+    if !issues.parse_errors.is_empty() {
+        let _ = writeln!(
+            html,
+            "<h2>Parse errors</h2>\n<p>{}</p>\n<ul class=\"issues\">",
+            escape(&issues.parse_error_summary())
+        );
+        for error in issues.parse_errors {
+            let _ = write!(
+                html,
+                "<li><code>{}:{}:{}</code> {}",
+                escape(&error.filename),
+                error.line,
+                error.col,
+                escape(&error.message)
+            );
+            if let Some(line) = source_cache.line(&error.filename, error.line) {
+                let _ = write!(
+                    html,
+                    "<pre><span class=\"ln\">{}</span>{}</pre>",
+                    error.line,
+                    escape(line)
+                );
+            }
+            html.push_str("</li>\n");
+        }
+        html.push_str("</ul>\n");
+    }
+    if !issues.skipped.is_empty() {
+        html.push_str("<h2>Skipped files</h2>\n<ul class=\"issues\">\n");
+        for (filename, err) in issues.skipped {
+            let _ = writeln!(
+                html,
+                "<li><code>{}</code> {}</li>",
+                escape(filename),
+                escape(&err.to_string())
+            );
+        }
+        html.push_str("</ul>\n");
+    }
+    html
+}
+
+/// Both copies of a finding side by side, then how their bodies differ.
+fn render_pair(out: &mut String, finding: &Finding, source_cache: &FileCache, issues: ScanIssues) {
+    let verdict = match (finding.severity, finding.documented_copy()) {
+        (Severity::Critical, _) => "identical body",
+        (Severity::Warning, Some(_)) => "documentation out of date",
+        (Severity::Warning, None) => "different body",
+    };
+    let _ = write!(
+        out,
+        "<section class=\"pair\">\n<h3>{} ↔ {} <small>{}</small></h3>\n<div class=\"side-by-side\">\n",
+        escape(&location(&finding.first)),
+        escape(&location(&finding.second)),
+        verdict
+    );
+    if let Some(note) = issues.incomplete_note(finding) {
+        let _ = writeln!(out, "<p class=\"note\">{}</p>", escape(&note));
+    }
+    for node in [&finding.first, &finding.second] {
+        let _ = writeln!(
+            out,
+            "<figure><figcaption>{}</figcaption><pre>{}</pre></figure>",
+            escape(&location(node)),
+            excerpt(node, source_cache)
+        );
+    }
+    out.push_str("</div>\n");
+
+    if finding.severity == Severity::Warning {
+        out.push_str("<pre class=\"diff\">");
+        for line in body_diff(finding).lines() {
+            let class = if line.starts_with("+ ") {
+                "add"
+            } else if line.starts_with("- ") {
+                "del"
+            } else {
+                "ctx"
+            };
+            let _ = write!(out, "<span class=\"{}\">{}</span>", class, escape(line));
+        }
+        out.push_str("</pre>\n");
+    }
+    out.push_str("</section>\n");
+}
+
+fn location(node: &FoundDeclarationNode) -> String {
+    format!("{}:{}", node.filename, node.line)
+}
+
+/// The whole lines `node` spans, numbered, or its serialized body when the
+/// source isn't cached.
+fn excerpt(node: &FoundDeclarationNode, source_cache: &FileCache) -> String {
+    let Some(lines) = source_cache.excerpt(node) else {
+        return escape(&node.body);
+    };
+    let mut html = String::new();
+    for (i, line) in lines.lines().enumerate() {
+        let _ = writeln!(
+            html,
+            "<span class=\"ln\">{}</span>{}",
+            node.line + i,
+            escape(line)
+        );
+    }
+    html
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+const STYLE: &str = r#"
+body { font: 14px/1.4 system-ui, sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; width: 100%; }
+th { text-align: left; cursor: pointer; user-select: none; border-bottom: 2px solid #ccc; padding: 4px 8px; }
+th.asc::after { content: " ▲"; } th.desc::after { content: " ▼"; }
+td { padding: 4px 8px; border-bottom: 1px solid #eee; vertical-align: top; }
+tr.summary { cursor: pointer; } tr.summary:hover { background: #f6f6f6; }
+.badge { border-radius: 3px; padding: 1px 6px; font-size: 12px; color: #fff; }
+.badge.critical { background: #c62828; } .badge.warning { background: #ef8c00; }
+.filters { margin: 1em 0; } .filters label { margin-right: 1em; }
+.pair { margin: 0.5em 0 1.5em; } .pair h3 { font-size: 14px; margin: 0.5em 0; }
+.pair small { color: #777; font-weight: normal; }
+.side-by-side { display: grid; grid-template-columns: 1fr 1fr; gap: 1em; }
+figure { margin: 0; min-width: 0; } figcaption { font-size: 12px; color: #555; }
+pre { background: #f7f7f7; padding: 8px; overflow-x: auto; margin: 4px 0; }
+.ln { display: inline-block; width: 3em; color: #999; user-select: none; }
+.diff span { display: block; }
+.note { color: #8a5a00; margin: 4px 0; } .issues li { margin-bottom: 0.5em; }
+.diff .add { color: #1b5e20; background: #e8f5e9; } .diff .del { color: #b71c1c; background: #ffebee; }
+"#;
+
+const SCRIPT: &str = r#"
+const table = document.getElementById("groups");
+const groups = () => Array.from(table.tBodies);
+table.addEventListener("click", (event) => {
+  const summary = event.target.closest("tr.summary");
+  if (summary) summary.nextElementSibling.hidden = !summary.nextElementSibling.hidden;
+});
+table.tHead.querySelectorAll("th").forEach((th, column) => {
+  th.addEventListener("click", () => {
+    const ascending = !th.classList.contains("asc");
+    table.tHead.querySelectorAll("th").forEach((other) => other.classList.remove("asc", "desc"));
+    th.classList.add(ascending ? "asc" : "desc");
+    const key = (group) => group.rows[0].cells[column].dataset.key;
+    const sorted = groups().sort((a, b) => {
+      const [x, y] = [key(a), key(b)];
+      const order = isNaN(x) || isNaN(y) ? x.localeCompare(y) : x - y;
+      return ascending ? order : -order;
+    });
+    sorted.forEach((group) => table.appendChild(group));
+  });
+});
+const filter = () => {
+  const severity = document.getElementById("severity").value;
+  const project = document.getElementById("project");
+  const wanted = project ? project.value : "";
+  groups().forEach((group) => {
+    const projects = group.dataset.projects.split("|");
+    group.hidden = (severity && group.dataset.severity !== severity)
+      || (wanted && !projects.includes(wanted));
+  });
+};
+document.querySelectorAll(".filters select").forEach((select) => select.addEventListener("change", filter));
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use tta::parser::ParseError;
+    use tta::Analyzer;
+
+    #[test]
+    fn test_groups_findings_by_name() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a.ts", "interface Box<T> { x: T }");
+        analyzer.add_source("b.ts", "interface Box<T> { x: T }");
+        analyzer.add_source("c.ts", "interface Box<T> { y: T }");
+        let mut cache = FileCache::new();
+        cache.insert("a.ts".to_string(), "interface Box<T> { x: T }".to_string());
+        let page = render_html(
+            &analyzer.findings(),
+            &cache,
+            ScanIssues::default(),
+            false,
+            &|_| None,
+        );
+
+        assert_eq!(page.matches("<tbody class=\"group\"").count(), 1);
+        assert_eq!(page.matches("<section class=\"pair\">").count(), 3);
+        assert!(page.contains("<span class=\"ln\">1</span>interface Box&lt;T&gt; { x: T }"));
+        assert!(page.contains("<span class=\"del\">- x: T</span>"));
+        assert!(!page.contains("id=\"project\""));
+    }
+
+    #[test]
+    fn test_lists_parse_errors_and_skipped_files() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a.ts", "interface A { x: string }\nconst a;");
+        analyzer.add_source("b.ts", "interface A { x: string }");
+        let parse_errors: Vec<&ParseError> = analyzer.index().parse_errors().collect();
+        let skipped = [(
+            "c.ts".to_string(),
+            io::Error::new(io::ErrorKind::PermissionDenied, "permission denied"),
+        )];
+        let mut cache = FileCache::new();
+        cache.insert(
+            "a.ts".to_string(),
+            "interface A { x: string }\nconst a;".to_string(),
+        );
+        let issues = ScanIssues {
+            parse_errors: &parse_errors,
+            skipped: &skipped,
+        };
+        let page = render_html(&analyzer.findings(), &cache, issues, false, &|_| None);
+
+        assert!(page.contains(
+            "<p class=\"note\">a.ts has parse errors; duplicates involving it may be incomplete.</p>"
+        ));
+        assert!(page.contains("<h2>Parse errors</h2>\n<p>1 parse error in 1 file;"));
+        assert!(page.contains("<pre><span class=\"ln\">2</span>const a;</pre>"));
+        assert!(page.contains("<li><code>c.ts</code> permission denied</li>"));
+    }
+}
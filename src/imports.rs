@@ -0,0 +1,835 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use crate::analyzer::Finding;
+use crate::files::{absolute, normalize, relative_to_cwd};
+use crate::index::DeclarationIndex;
+use crate::parser::{ImportRecord, ImportedName};
+use crate::shared_lib::FoundDeclarationNode;
+use crate::tsconfig::TsConfig;
+
+/// Extensions tried, in order, for a specifier without one.
+const EXTENSIONS: [&str; 5] = [".ts", ".tsx", ".d.ts", ".mts", ".cts"];
+
+/// Turns module specifiers into file paths the way the TypeScript compiler
+/// does for `moduleResolution: bundler`: relative paths, `paths` and `baseUrl`
+/// from a tsconfig, `node_modules` packages (`exports`, `types`, `main`) and
+/// directory index files.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleResolver {
+    base_url: Option<PathBuf>,
+    /// `paths` entries as `(pattern, targets)`, targets already resolved.
+    paths: Vec<(String, Vec<PathBuf>)>,
+}
+
+impl ModuleResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Picks up `baseUrl` and `paths` from `config`.
+    pub fn from_tsconfig(config: &TsConfig) -> Self {
+        let base_url = config.option_path("baseUrl");
+        // `paths` are relative to `baseUrl` when set, else to the config declaring them.
+        let paths_dir = base_url
+            .clone()
+            .unwrap_or_else(|| config.option_dir("paths"));
+        let paths = config
+            .compiler_options
+            .get("paths")
+            .and_then(Value::as_object)
+            .map(|paths| {
+                paths
+                    .iter()
+                    .map(|(pattern, targets)| {
+                        let targets = targets
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .filter_map(Value::as_str)
+                            .map(|target| normalize(&paths_dir.join(target)))
+                            .collect();
+                        (pattern.clone(), targets)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        ModuleResolver { base_url, paths }
+    }
+
+    /// Resolves `specifier` as imported from `from`. `exists` decides whether a
+    /// candidate file is there, so sources that only live in memory resolve too.
+    /// Paths below the working directory come back relative to it, like the
+    /// paths `find_ts_files` returns.
+    pub fn resolve(
+        &self,
+        from: &str,
+        specifier: &str,
+        exists: &dyn Fn(&Path) -> bool,
+    ) -> Option<String> {
+        let from_dir = Path::new(from).parent().unwrap_or(Path::new(""));
+
+        let resolved = if specifier.starts_with("./")
+            || specifier.starts_with("../")
+            || specifier == "."
+            || specifier == ".."
+        {
+            resolve_path(&normalize(&from_dir.join(specifier)), exists)
+        } else if Path::new(specifier).is_absolute() {
+            resolve_path(Path::new(specifier), exists)
+        } else {
+            self.resolve_paths(specifier, exists)
+                .or_else(|| {
+                    let base_url = self.base_url.as_ref()?;
+                    resolve_path(&base_url.join(specifier), exists)
+                })
+                .or_else(|| resolve_package(from_dir, specifier, exists))
+        }?;
+
+        Some(relative_to_cwd(&resolved).to_string_lossy().to_string())
+    }
+
+    /// The specifier `from` should use to import `target`: a tsconfig `paths`
+    /// alias when one covers it, else a relative path. Extensions and trailing
+    /// `/index` segments are dropped.
+    pub fn specifier(&self, from: &str, target: &str) -> String {
+        let target = absolute(Path::new(target));
+        let stem = module_stem(&target);
+
+        // The mapping with the most specific target directory wins.
+        let mut best: Option<(usize, String)> = None;
+        for (pattern, targets) in &self.paths {
+            let Some((alias_prefix, alias_suffix)) = pattern.split_once('*') else {
+                continue;
+            };
+            for mapped in targets {
+                let mapped = absolute(mapped).to_string_lossy().to_string();
+                let Some((prefix, suffix)) = mapped.split_once('*') else {
+                    continue;
+                };
+                let suffix = suffix.trim_end_matches(".ts");
+                let Some(star) = stem
+                    .strip_prefix(prefix)
+                    .and_then(|rest| rest.strip_suffix(suffix))
+                else {
+                    continue;
+                };
+                if best.as_ref().is_none_or(|(len, _)| prefix.len() > *len) {
+                    let star = star.strip_suffix("/index").unwrap_or(star);
+                    best = Some((
+                        prefix.len(),
+                        format!("{}{}{}", alias_prefix, star, alias_suffix),
+                    ));
+                }
+            }
+        }
+        if let Some((_, alias)) = best {
+            return alias;
+        }
+
+        let from_dir = absolute(Path::new(from))
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let relative = relative_path(&from_dir, Path::new(&stem));
+        let relative = relative.strip_suffix("/index").unwrap_or(&relative);
+        if relative.starts_with("../") {
+            relative.to_string()
+        } else {
+            format!("./{}", relative)
+        }
+    }
+
+    fn resolve_paths(&self, specifier: &str, exists: &dyn Fn(&Path) -> bool) -> Option<PathBuf> {
+        // The pattern with the longest prefix before `*` wins, exact matches first.
+        let mut matches: Vec<(usize, &str, &Vec<PathBuf>)> = self
+            .paths
+            .iter()
+            .filter_map(|(pattern, targets)| match pattern.split_once('*') {
+                Some((prefix, suffix)) => {
+                    let rest = specifier.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                    Some((prefix.len(), rest, targets))
+                }
+                None => (pattern == specifier).then_some((usize::MAX, "", targets)),
+            })
+            .collect();
+        matches.sort_by_key(|(prefix_len, _, _)| std::cmp::Reverse(*prefix_len));
+
+        matches.into_iter().find_map(|(_, star, targets)| {
+            targets.iter().find_map(|target| {
+                let target = target.to_string_lossy().replacen('*', star, 1);
+                resolve_path(Path::new(&target), exists)
+            })
+        })
+    }
+}
+
+/// `path` without its TypeScript extension; `.mts`/`.cts` become the
+/// `.mjs`/`.cjs` the compiler expects in specifiers.
+fn module_stem(path: &Path) -> String {
+    let text = path.to_string_lossy();
+    for (extension, replacement) in [
+        (".d.ts", ""),
+        (".tsx", ""),
+        (".ts", ""),
+        (".mts", ".mjs"),
+        (".cts", ".cjs"),
+    ] {
+        if let Some(stem) = text.strip_suffix(extension) {
+            return format!("{}{}", stem, replacement);
+        }
+    }
+    text.to_string()
+}
+
+/// `to` relative to the directory `from`, both absolute.
+fn relative_path(from: &Path, to: &Path) -> String {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - common];
+    parts.extend(
+        to[common..]
+            .iter()
+            .map(|component| component.as_os_str().to_string_lossy().to_string()),
+    );
+    parts.join("/")
+}
+
+/// Tries `path` as a file, with a TypeScript extension added or swapped in for
+/// a JavaScript one, and as a directory with an index file or `package.json`.
+fn resolve_path(path: &Path, exists: &dyn Fn(&Path) -> bool) -> Option<PathBuf> {
+    let text = path.to_string_lossy();
+    let mut candidates = Vec::new();
+
+    if crate::files::is_ts_file(path) || crate::files::is_component_file(path) {
+        candidates.push(path.to_path_buf());
+    }
+    for (js, ts) in [
+        (".js", ".ts"),
+        (".jsx", ".tsx"),
+        (".mjs", ".mts"),
+        (".cjs", ".cts"),
+    ] {
+        if let Some(stem) = text.strip_suffix(js) {
+            candidates.push(PathBuf::from(format!("{}{}", stem, ts)));
+            if js == ".js" {
+                candidates.push(PathBuf::from(format!("{}.tsx", stem)));
+                candidates.push(PathBuf::from(format!("{}.d.ts", stem)));
+            }
+        }
+    }
+    for extension in EXTENSIONS {
+        candidates.push(PathBuf::from(format!("{}{}", text, extension)));
+    }
+
+    if let Some(found) = candidates.into_iter().find(|candidate| exists(candidate)) {
+        return Some(found);
+    }
+
+    if let Some(entry) = read_package(path).and_then(|package| package_entry(&package, ".")) {
+        let entry = normalize(&path.join(entry));
+        if entry != path {
+            if let Some(found) = resolve_path(&entry, exists) {
+                return Some(found);
+            }
+        }
+    }
+
+    EXTENSIONS
+        .iter()
+        .map(|extension| path.join(format!("index{}", extension)))
+        .find(|candidate| exists(candidate))
+}
+
+/// Looks `specifier` up in `node_modules` folders from `from_dir` upwards.
+fn resolve_package(
+    from_dir: &Path,
+    specifier: &str,
+    exists: &dyn Fn(&Path) -> bool,
+) -> Option<PathBuf> {
+    // `@scope/name/sub/path` splits into the package and the subpath.
+    let segments = if specifier.starts_with('@') { 2 } else { 1 };
+    let mut parts = specifier.splitn(segments + 1, '/');
+    let package_name: Vec<&str> = parts.by_ref().take(segments).collect();
+    let package_name = package_name.join("/");
+    let subpath = match parts.next() {
+        Some(rest) => format!("./{}", rest),
+        None => ".".to_string(),
+    };
+
+    absolute(from_dir).ancestors().find_map(|ancestor| {
+        let root = ancestor.join("node_modules").join(&package_name);
+        if !root.is_dir() {
+            return None;
+        }
+        // Workspace packages are usually symlinked; report them at their real path.
+        let root = std::fs::canonicalize(&root).unwrap_or(root);
+
+        let exported = read_package(&root)
+            .and_then(|package| package_entry(&package, &subpath))
+            .and_then(|entry| resolve_path(&normalize(&root.join(entry)), exists));
+        exported.or_else(|| resolve_path(&normalize(&root.join(&subpath)), exists))
+    })
+}
+
+fn read_package(dir: &Path) -> Option<Value> {
+    let contents = std::fs::read_to_string(dir.join("package.json")).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// The file a package exposes for `subpath` (`.` for the package itself),
+/// from `exports` or, for the root, `types`/`typings`/`main`.
+fn package_entry(package: &Value, subpath: &str) -> Option<String> {
+    if let Some(exports) = package.get("exports") {
+        let target = match exports {
+            Value::Object(map) if map.keys().any(|key| key.starts_with('.')) => {
+                map.get(subpath).cloned().or_else(|| {
+                    // `./*` style subpath patterns.
+                    map.iter().find_map(|(key, value)| {
+                        let (prefix, suffix) = key.split_once('*')?;
+                        let star = subpath.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                        Some(substitute(value, star))
+                    })
+                })
+            }
+            _ if subpath == "." => Some(exports.clone()),
+            _ => None,
+        };
+        if let Some(entry) = target.as_ref().and_then(export_condition) {
+            return Some(entry);
+        }
+    }
+
+    if subpath != "." {
+        return None;
+    }
+    ["types", "typings", "main"]
+        .iter()
+        .find_map(|field| package.get(field)?.as_str().map(str::to_string))
+}
+
+/// Picks the entry of an `exports` target, preferring type declarations.
+fn export_condition(target: &Value) -> Option<String> {
+    match target {
+        Value::String(entry) => Some(entry.clone()),
+        Value::Array(entries) => entries.iter().find_map(export_condition),
+        Value::Object(conditions) => ["types", "import", "default", "require", "node"]
+            .iter()
+            .find_map(|condition| export_condition(conditions.get(*condition)?)),
+        _ => None,
+    }
+}
+
+fn substitute(value: &Value, star: &str) -> Value {
+    match value {
+        Value::String(entry) => Value::String(entry.replace('*', star)),
+        Value::Array(entries) => entries.iter().map(|v| substitute(v, star)).collect(),
+        Value::Object(conditions) => conditions
+            .iter()
+            .map(|(key, v)| (key.clone(), substitute(v, star)))
+            .collect(),
+        other => other.clone(),
+    }
+}
+
+/// The declaration `name` refers to when used in `filename`: one declared in
+/// the same file, else the one it is imported from (when `imports` is given),
+/// else the only declaration with that name.
+pub fn resolve_declaration<'d>(
+    declarations: &'d HashMap<String, Vec<FoundDeclarationNode>>,
+    imports: Option<&ImportGraph>,
+    filename: &str,
+    name: &str,
+) -> Option<&'d FoundDeclarationNode> {
+    let candidates = declarations.get(name);
+    if let Some(local) = candidates.and_then(|c| c.iter().find(|d| d.filename == filename)) {
+        return Some(local);
+    }
+
+    if let Some((origin, imported)) =
+        imports.and_then(|imports| imports.resolve_local(filename, name))
+    {
+        return declarations
+            .get(&imported)?
+            .iter()
+            .find(|d| d.filename == origin);
+    }
+
+    match candidates {
+        Some(candidates) if candidates.len() == 1 => candidates.first(),
+        _ => None,
+    }
+}
+
+/// An import statement with its specifier resolved to a file.
+#[derive(Debug, Clone)]
+struct ResolvedImport {
+    target: String,
+    record: ImportRecord,
+}
+
+/// A module that imports both declarations of a duplicate pair, with the chain
+/// of files each import goes through (importer first, declaring file last).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SharedImporter {
+    pub module: String,
+    pub first_chain: Vec<String>,
+    pub second_chain: Vec<String>,
+}
+
+/// Which file imports what from where, across every indexed source.
+#[derive(Debug, Default)]
+pub struct ImportGraph {
+    declared: HashMap<String, HashSet<String>>,
+    imports: BTreeMap<String, Vec<ResolvedImport>>,
+    /// Import edges (importing module, position in its imports) by the name
+    /// they import; namespace and `export *` edges, which may carry any name,
+    /// are kept under `None`.
+    importing: HashMap<Option<String>, Vec<(String, usize)>>,
+}
+
+impl ImportGraph {
+    /// Resolves the imports of every file in `index`. Imports that don't resolve
+    /// (missing packages, non-TypeScript assets) are left out.
+    pub fn build(index: &DeclarationIndex, resolver: &ModuleResolver) -> ImportGraph {
+        let known: HashSet<&str> = index.filenames().map(String::as_str).collect();
+        let exists =
+            |path: &Path| known.contains(path.to_string_lossy().as_ref()) || path.is_file();
+        // `paths`, `baseUrl` and packages resolve to absolute paths when the scan
+        // started above the working directory; map them back onto index keys.
+        let by_absolute: HashMap<PathBuf, &str> = known
+            .iter()
+            .map(|filename| (absolute(Path::new(filename)), *filename))
+            .collect();
+
+        let mut graph = ImportGraph::default();
+        for filename in index.filenames() {
+            graph.declared.insert(
+                filename.clone(),
+                index.declared_names(filename).iter().cloned().collect(),
+            );
+            let resolved = index
+                .imports(filename)
+                .iter()
+                .filter_map(|record| {
+                    let mut target = resolver.resolve(filename, &record.specifier, &exists)?;
+                    if !known.contains(target.as_str()) {
+                        if let Some(key) = by_absolute.get(&absolute(Path::new(&target))) {
+                            target = key.to_string();
+                        }
+                    }
+                    Some(ResolvedImport {
+                        target,
+                        record: record.clone(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            for (position, import) in resolved.iter().enumerate() {
+                for imported in &import.record.names {
+                    let key = match imported {
+                        ImportedName::Named { imported, .. } => Some(imported.clone()),
+                        ImportedName::Namespace(_) | ImportedName::All => None,
+                    };
+                    graph
+                        .importing
+                        .entry(key)
+                        .or_default()
+                        .push((filename.clone(), position));
+                }
+            }
+            graph.imports.insert(filename.clone(), resolved);
+        }
+        graph
+    }
+
+    /// The file `specifier` resolves to when imported from `filename`.
+    pub fn target(&self, filename: &str, specifier: &str) -> Option<&str> {
+        self.imports
+            .get(filename)?
+            .iter()
+            .find(|import| import.record.specifier == specifier)
+            .map(|import| import.target.as_str())
+    }
+
+    /// Files `filename` imports from, resolved.
+    pub fn dependencies(&self, filename: &str) -> Vec<&str> {
+        self.imports
+            .get(filename)
+            .into_iter()
+            .flatten()
+            .map(|import| import.target.as_str())
+            .collect()
+    }
+
+    /// Follows re-exports of `name` from `filename` back to the file declaring
+    /// it. Returns the chain of files, starting with `filename`. Files outside
+    /// the index are taken to declare what is imported from them.
+    pub fn origin(&self, filename: &str, name: &str) -> Option<Vec<String>> {
+        self.origin_from(filename, name, &mut HashSet::new())
+    }
+
+    fn origin_from(
+        &self,
+        filename: &str,
+        name: &str,
+        visited: &mut HashSet<(String, String)>,
+    ) -> Option<Vec<String>> {
+        if !visited.insert((filename.to_string(), name.to_string())) {
+            return None;
+        }
+        let Some(declared) = self.declared.get(filename) else {
+            return Some(vec![filename.to_string()]);
+        };
+        if declared.contains(name) {
+            return Some(vec![filename.to_string()]);
+        }
+
+        let imports = self.imports.get(filename)?;
+        imports
+            .iter()
+            .filter(|import| import.record.is_reexport)
+            .find_map(|import| {
+                let imported = import
+                    .record
+                    .names
+                    .iter()
+                    .find_map(|imported| match imported {
+                        ImportedName::Named { imported, local } if local == name => {
+                            Some(imported.as_str())
+                        }
+                        ImportedName::All if name != "default" => Some(name),
+                        _ => None,
+                    })?;
+                let mut chain = self.origin_from(&import.target, imported, visited)?;
+                chain.insert(0, filename.to_string());
+                Some(chain)
+            })
+    }
+
+    /// The file and name a local binding `local` in `filename` was imported
+    /// as, followed through barrel files to the declaration.
+    pub fn resolve_local(&self, filename: &str, local: &str) -> Option<(String, String)> {
+        self.imports
+            .get(filename)?
+            .iter()
+            .filter(|import| !import.record.is_reexport)
+            .find_map(|import| {
+                let imported = import.record.names.iter().find_map(|name| match name {
+                    ImportedName::Named {
+                        imported,
+                        local: bound,
+                    } if bound == local => Some(imported.as_str()),
+                    _ => None,
+                })?;
+                let chain = self.origin(&import.target, imported)?;
+                Some((chain.last()?.clone(), imported.to_string()))
+            })
+    }
+
+    /// The file and name `namespace.name` refers to in `filename`, where
+    /// `namespace` is bound by `import * as namespace`.
+    pub fn resolve_namespace_member(
+        &self,
+        filename: &str,
+        namespace: &str,
+        name: &str,
+    ) -> Option<(String, String)> {
+        self.imports
+            .get(filename)?
+            .iter()
+            .filter(|import| !import.record.is_reexport)
+            .filter(|import| {
+                import
+                    .record
+                    .names
+                    .iter()
+                    .any(|bound| *bound == ImportedName::Namespace(namespace.to_string()))
+            })
+            .find_map(|import| {
+                let chain = self.origin(&import.target, name)?;
+                Some((chain.last()?.clone(), name.to_string()))
+            })
+    }
+
+    /// Every module that imports `name` as declared in `filename`, directly or
+    /// through barrel files, each with its import chain. Re-exports are
+    /// followed under the name they export, so `export { User as Account }`
+    /// reaches the modules importing `Account`. The declaring file itself
+    /// counts as importing its own declaration.
+    pub fn importers(&self, filename: &str, name: &str) -> BTreeMap<String, Vec<String>> {
+        let mut importers = BTreeMap::new();
+        importers.insert(filename.to_string(), vec![filename.to_string()]);
+
+        let mut visited = HashSet::new();
+        let mut pending = VecDeque::from([(
+            filename.to_string(),
+            name.to_string(),
+            vec![filename.to_string()],
+        )]);
+        while let Some((file, exported, chain)) = pending.pop_front() {
+            if !visited.insert((file.clone(), exported.clone())) {
+                continue;
+            }
+            let mut edges: Vec<&(String, usize)> = [Some(exported.clone()), None]
+                .iter()
+                .filter_map(|key| self.importing.get(key))
+                .flatten()
+                .collect();
+            edges.sort();
+            edges.dedup();
+            for (module, position) in edges {
+                let import = &self.imports[module][*position];
+                if import.target != file {
+                    continue;
+                }
+                let mut module_chain = chain.clone();
+                module_chain.insert(0, module.clone());
+                for imported in &import.record.names {
+                    // The name `module` makes the declaration available under,
+                    // if it passes it on.
+                    let forwarded = match imported {
+                        ImportedName::Named { imported, local } if *imported == exported => {
+                            Some(local)
+                        }
+                        ImportedName::Namespace(_) => None,
+                        ImportedName::All
+                            if exported != "default" && !self.declares(module, &exported) =>
+                        {
+                            Some(&exported)
+                        }
+                        _ => continue,
+                    };
+                    if let Some(local) = forwarded.filter(|_| import.record.is_reexport) {
+                        pending.push_back((module.clone(), local.clone(), module_chain.clone()));
+                    }
+                    importers
+                        .entry(module.clone())
+                        .or_insert_with(|| module_chain.clone());
+                }
+            }
+        }
+        importers
+    }
+
+    fn declares(&self, filename: &str, name: &str) -> bool {
+        self.declared
+            .get(filename)
+            .is_some_and(|declared| declared.contains(name))
+    }
+
+    /// The first module (by path) that both declarations of `finding` reach.
+    pub fn shared_importer(&self, finding: &Finding) -> Option<SharedImporter> {
+        let first = self.importers(&finding.first.filename, &finding.name);
+        let second = self.importers(&finding.second.filename, &finding.name);
+
+        first.into_iter().find_map(|(module, first_chain)| {
+            let second_chain = second.get(&module)?.clone();
+            Some(SharedImporter {
+                module,
+                first_chain,
+                second_chain,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Analyzer;
+
+    fn in_memory<'a>(files: &'a [&'a str]) -> impl Fn(&Path) -> bool + 'a {
+        move |path: &Path| files.contains(&path.to_string_lossy().as_ref())
+    }
+
+    #[test]
+    fn test_resolve_relative_and_index() {
+        let resolver = ModuleResolver::new();
+        let exists = in_memory(&["src/models/user.ts", "src/models/index.ts"]);
+
+        assert_eq!(
+            resolver.resolve("src/app.ts", "./models/user", &exists),
+            Some("src/models/user.ts".to_string())
+        );
+        assert_eq!(
+            resolver.resolve("src/app.ts", "./models/user.js", &exists),
+            Some("src/models/user.ts".to_string())
+        );
+        assert_eq!(
+            resolver.resolve("src/app.ts", "./models", &exists),
+            Some("src/models/index.ts".to_string())
+        );
+        assert_eq!(resolver.resolve("src/app.ts", "./missing", &exists), None);
+    }
+
+    #[test]
+    fn test_resolve_paths_mapping() {
+        let resolver = ModuleResolver {
+            base_url: None,
+            paths: vec![
+                ("@app/*".to_string(), vec![PathBuf::from("src/*")]),
+                (
+                    "@app/models/*".to_string(),
+                    vec![PathBuf::from("libs/models/*")],
+                ),
+            ],
+        };
+        let exists = in_memory(&["src/util.ts", "libs/models/user.ts"]);
+
+        assert_eq!(
+            resolver.resolve("src/app.ts", "@app/util", &exists),
+            Some("src/util.ts".to_string())
+        );
+        assert_eq!(
+            resolver.resolve("src/app.ts", "@app/models/user", &exists),
+            Some("libs/models/user.ts".to_string())
+        );
+    }
+
+    #[test]
+    fn test_specifier_prefers_alias_then_relative() {
+        let resolver = ModuleResolver {
+            base_url: None,
+            paths: vec![
+                ("@app/*".to_string(), vec![PathBuf::from("src/*")]),
+                (
+                    "@app/models/*".to_string(),
+                    vec![PathBuf::from("libs/models/*")],
+                ),
+            ],
+        };
+
+        assert_eq!(
+            resolver.specifier("other/a.ts", "libs/models/user.ts"),
+            "@app/models/user"
+        );
+        assert_eq!(
+            resolver.specifier("other/a.ts", "src/shared/index.ts"),
+            "@app/shared"
+        );
+        assert_eq!(
+            ModuleResolver::new().specifier("src/a/b.ts", "src/c/types.d.ts"),
+            "../c/types"
+        );
+        assert_eq!(
+            ModuleResolver::new().specifier("src/a.ts", "src/user.mts"),
+            "./user.mjs"
+        );
+    }
+
+    #[test]
+    fn test_package_entry_prefers_types_condition() {
+        let package: Value = serde_json::from_str(
+            r#"{
+                "main": "dist/index.js",
+                "exports": {
+                    ".": { "types": "./dist/index.d.ts", "default": "./dist/index.js" },
+                    "./utils/*": { "types": "./dist/utils/*.d.ts" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            package_entry(&package, "."),
+            Some("./dist/index.d.ts".to_string())
+        );
+        assert_eq!(
+            package_entry(&package, "./utils/date"),
+            Some("./dist/utils/date.d.ts".to_string())
+        );
+        assert_eq!(package_entry(&package, "./missing"), None);
+    }
+
+    #[test]
+    fn test_shared_importer_through_barrel() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("models/user.ts", "export interface User { id: string }");
+        analyzer.add_source("models/index.ts", "export * from './user';");
+        analyzer.add_source("legacy/user.ts", "export interface User { id: string }");
+        analyzer.add_source(
+            "app/page.ts",
+            "import { User } from '../models';\nimport { User as OldUser } from '../legacy/user';",
+        );
+        analyzer.add_source("app/other.ts", "import { User } from '../models/user';");
+
+        let graph = analyzer.import_graph();
+        assert_eq!(
+            graph.origin("models/index.ts", "User"),
+            Some(vec![
+                "models/index.ts".to_string(),
+                "models/user.ts".to_string()
+            ])
+        );
+
+        let findings = analyzer.findings();
+        assert_eq!(findings.len(), 1);
+        let shared = graph.shared_importer(&findings[0]).unwrap();
+        assert_eq!(shared.module, "app/page.ts");
+        assert_eq!(
+            shared.first_chain,
+            vec!["app/page.ts", "models/index.ts", "models/user.ts"]
+        );
+        assert_eq!(shared.second_chain, vec!["app/page.ts", "legacy/user.ts"]);
+    }
+
+    #[test]
+    fn test_no_shared_importer_when_imported_separately() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a/user.ts", "export interface User { id: string }");
+        analyzer.add_source("b/user.ts", "export interface User { id: string }");
+        analyzer.add_source("a/page.ts", "import { User } from './user';");
+        analyzer.add_source("b/page.ts", "import type { User } from './user';");
+
+        let graph = analyzer.import_graph();
+        let findings = analyzer.findings();
+        assert_eq!(graph.shared_importer(&findings[0]), None);
+        assert_eq!(graph.dependencies("a/page.ts"), vec!["a/user.ts"]);
+    }
+
+    #[test]
+    fn test_importers_through_aliased_reexport() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("lib/user.ts", "export interface User { id: string }");
+        analyzer.add_source(
+            "lib/index.ts",
+            "export { User as Account } from './user';\nexport * from './user';",
+        );
+        analyzer.add_source("a.ts", "import { Account } from './lib';");
+        analyzer.add_source("b.ts", "import { User } from './lib';");
+        analyzer.add_source("c.ts", "import { Other } from './lib';");
+
+        let graph = analyzer.import_graph();
+        let importers = graph.importers("lib/user.ts", "User");
+        assert_eq!(
+            importers.keys().collect::<Vec<_>>(),
+            vec!["a.ts", "b.ts", "lib/index.ts", "lib/user.ts"]
+        );
+        assert_eq!(
+            importers["a.ts"],
+            vec!["a.ts", "lib/index.ts", "lib/user.ts"]
+        );
+    }
+
+    #[test]
+    fn test_importers_by_name_and_namespace() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("user.ts", "export interface User { id: string }");
+        analyzer.add_source("a.ts", "import { User as U } from './user';");
+        analyzer.add_source("b.ts", "import * as models from './user';");
+        analyzer.add_source("c.ts", "import { Other } from './user';");
+
+        let graph = analyzer.import_graph();
+        let importers = graph.importers("user.ts", "User");
+        assert_eq!(
+            importers.keys().collect::<Vec<_>>(),
+            vec!["a.ts", "b.ts", "user.ts"]
+        );
+        assert_eq!(importers["b.ts"], vec!["b.ts", "user.ts"]);
+    }
+}
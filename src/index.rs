@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use crate::parser::{parse_source, ImportRecord, ParseError, ParsedFile};
+use crate::shared_lib::{FoundDeclarationNode, Implementation};
+use crate::usage::Usage;
+
+/// What a single file contributed to the index, so it can be taken back out.
+struct FileEntry {
+    names: Vec<String>,
+    implementations: Vec<Implementation>,
+    usages: Vec<Usage>,
+    imports: Vec<ImportRecord>,
+    errors: Vec<ParseError>,
+}
+
+/// Declaration index that can be updated one file at a time, so long running
+/// modes (watch, lsp) don't have to rescan the whole tree on every change.
+#[derive(Default)]
+pub struct DeclarationIndex {
+    files: HashMap<String, FileEntry>,
+    results: HashMap<String, Vec<FoundDeclarationNode>>,
+}
+
+impl DeclarationIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn results(&self) -> &HashMap<String, Vec<FoundDeclarationNode>> {
+        &self.results
+    }
+
+    pub fn contains_file(&self, filename: &str) -> bool {
+        self.files.contains_key(filename)
+    }
+
+    pub fn filenames(&self) -> impl Iterator<Item = &String> {
+        self.files.keys()
+    }
+
+    /// Every `implements` clause entry across all files.
+    pub fn implementations(&self) -> impl Iterator<Item = &Implementation> {
+        self.files
+            .values()
+            .flat_map(|entry| entry.implementations.iter())
+    }
+
+    /// Every type reference across all files.
+    pub fn usages(&self) -> impl Iterator<Item = &Usage> {
+        self.files.values().flat_map(|entry| entry.usages.iter())
+    }
+
+    /// Names of the declarations `filename` contributed.
+    pub fn declared_names(&self, filename: &str) -> &[String] {
+        self.files
+            .get(filename)
+            .map(|entry| entry.names.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn imports(&self, filename: &str) -> &[ImportRecord] {
+        self.files
+            .get(filename)
+            .map(|entry| entry.imports.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Every syntax error across all files.
+    pub fn parse_errors(&self) -> impl Iterator<Item = &ParseError> {
+        self.files.values().flat_map(|entry| entry.errors.iter())
+    }
+
+    /// Whether oxc had to recover from errors in `filename`, so what it
+    /// contributed may be incomplete.
+    pub fn has_parse_errors(&self, filename: &str) -> bool {
+        self.files
+            .get(filename)
+            .is_some_and(|entry| !entry.errors.is_empty())
+    }
+
+    /// Parses `code` and replaces whatever `filename` previously contributed.
+    pub fn update_file(&mut self, filename: &str, code: &str) {
+        self.insert(filename, parse_source(code, filename, &[]));
+    }
+
+    /// Replaces whatever `filename` previously contributed with `parsed`.
+    pub fn insert(&mut self, filename: &str, parsed: ParsedFile) {
+        self.remove_file(filename);
+
+        let mut names: Vec<String> = Vec::new();
+        for found in parsed.declarations {
+            if !names.contains(&found.name) {
+                names.push(found.name.clone());
+            }
+            self.results
+                .entry(found.name.clone())
+                .or_default()
+                .push(found);
+        }
+
+        self.files.insert(
+            filename.to_string(),
+            FileEntry {
+                names,
+                implementations: parsed.implementations,
+                usages: parsed.usages,
+                imports: parsed.imports,
+                errors: parsed.errors,
+            },
+        );
+    }
+
+    /// Drops everything that came from `filename`.
+    pub fn remove_file(&mut self, filename: &str) {
+        let Some(entry) = self.files.remove(filename) else {
+            return;
+        };
+
+        for name in &entry.names {
+            if let Some(found) = self.results.get_mut(name) {
+                found.retain(|node| node.filename != filename);
+                if found.is_empty() {
+                    self.results.remove(name);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_update_adds_declarations() {
+        let mut index = DeclarationIndex::new();
+        index.update_file("a.ts", "interface IFoo { x: string; }");
+        index.update_file("b.ts", "interface IFoo { x: string; }");
+        assert_eq!(index.results().get("IFoo").map(Vec::len), Some(2));
+    }
+
+    #[test]
+    fn test_index_remove_file_drops_declarations() {
+        let mut index = DeclarationIndex::new();
+        index.update_file("a.ts", "interface IFoo { x: string; }");
+        index.update_file("b.ts", "interface IFoo { x: string; }");
+        index.remove_file("a.ts");
+
+        let found = index.results().get("IFoo").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].filename, "b.ts");
+
+        index.remove_file("b.ts");
+        assert!(index.results().get("IFoo").is_none());
+        assert!(!index.contains_file("b.ts"));
+    }
+
+    #[test]
+    fn test_index_update_replaces_previous_contents() {
+        let mut index = DeclarationIndex::new();
+        index.update_file("a.ts", "type Foo = string;");
+        index.update_file("a.ts", "type Bar = string;");
+        assert!(index.results().get("Foo").is_none());
+        assert_eq!(index.results().get("Bar").map(Vec::len), Some(1));
+    }
+
+    #[test]
+    fn test_index_implementations_follow_file_changes() {
+        let mut index = DeclarationIndex::new();
+        let count = |index: &DeclarationIndex| {
+            index
+                .implementations()
+                .filter(|imp| imp.interface == "IFoo")
+                .count()
+        };
+        index.update_file(
+            "a.ts",
+            "interface IFoo { x: string; } class A implements IFoo { x = 'a'; }",
+        );
+        index.update_file("b.ts", "class B implements IFoo { x = 'b'; }");
+        assert_eq!(count(&index), 2);
+
+        index.remove_file("b.ts");
+        assert_eq!(count(&index), 1);
+
+        index.update_file("a.ts", "interface IFoo { x: string; }");
+        assert_eq!(count(&index), 0);
+    }
+}
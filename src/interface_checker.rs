@@ -1,10 +1,12 @@
 use oxc::ast::ast::{PropertyKey, TSInterfaceDeclaration, TSSignature};
+use oxc::ast_visit::Visit;
 use oxc::span::Span;
 
+use crate::line_index::LineIndex;
 use crate::shared_lib::{
     byte_offset_to_line_col, AstNodeVariant, DeclarationChecker, FoundDeclarationNode,
 };
-use crate::type_checker::serialize_ts_type;
+use crate::type_checker::{serialize_ts_type, ReferenceCollector};
 
 pub struct InterfaceChecker<'a> {
     pub interface_decl: &'a TSInterfaceDeclaration<'a>,
@@ -18,15 +20,45 @@ impl<'a> DeclarationChecker for InterfaceChecker<'a> {
         is_exported: bool,
         override_span: Option<Span>,
     ) -> FoundDeclarationNode {
-        let name = self.interface_decl.id.name.to_string();
+        let span = override_span.unwrap_or(self.interface_decl.span);
+        let location = byte_offset_to_line_col(source, span.start as usize);
+        self.node(location, span, filename, is_exported)
+    }
+}
 
+impl InterfaceChecker<'_> {
+    /// [`DeclarationChecker::from_ast`], looking the location up in the line
+    /// index of the whole file instead of walking the source.
+    pub(crate) fn indexed(
+        &self,
+        lines: &LineIndex,
+        filename: &str,
+        is_exported: bool,
+        override_span: Option<Span>,
+    ) -> FoundDeclarationNode {
         let span = override_span.unwrap_or(self.interface_decl.span);
+        self.node(
+            lines.line_col(span.start as usize),
+            span,
+            filename,
+            is_exported,
+        )
+    }
+
+    fn node(
+        &self,
+        (line, col): (usize, usize),
+        span: Span,
+        filename: &str,
+        is_exported: bool,
+    ) -> FoundDeclarationNode {
+        let name = self.interface_decl.id.name.to_string();
         let start = span.start as usize;
         let end = span.end as usize;
 
-        let (line, col) = byte_offset_to_line_col(source, start);
-
         let body = serialize_interface_body(&self.interface_decl.body.body);
+        let mut references = ReferenceCollector::default();
+        references.visit_ts_interface_declaration(self.interface_decl);
 
         FoundDeclarationNode {
             ast_node_variant: AstNodeVariant::Interface,
@@ -38,6 +70,7 @@ impl<'a> DeclarationChecker for InterfaceChecker<'a> {
             span_start: start,
             span_end: end,
             is_exported,
+            references: references.finish(),
         }
     }
 }
@@ -46,11 +79,14 @@ fn serialize_property_key(key: &PropertyKey) -> String {
     match key {
         PropertyKey::StaticIdentifier(id) => id.name.to_string(),
         PropertyKey::PrivateIdentifier(id) => format!("#{}", id.name),
-        _ => format!("[{:?}]", key),
+        _ => match key.static_name() {
+            Some(name) => format!("[{:?}]", name.as_ref()),
+            None => "[computed]".to_string(),
+        },
     }
 }
 
-fn serialize_interface_body(members: &[TSSignature]) -> String {
+pub(crate) fn serialize_interface_body(members: &[TSSignature]) -> String {
     let parts: Vec<String> = members
         .iter()
         .map(|sig| match sig {
@@ -209,4 +245,13 @@ mod tests {
         let node = parse_interface("interface Foo { x: string; }");
         assert_eq!(node.filename, "test.ts");
     }
+
+    #[test]
+    fn test_interface_checker_method_type_parameters_only_bind_the_method() {
+        let node = parse_interface("interface Foo { a: T; m<T>(x: T): void }");
+        assert_eq!(node.references, vec!["T"]);
+
+        let node = parse_interface("interface Foo<T> { a: T; m<U>(x: U): Bar<T> }");
+        assert_eq!(node.references, vec!["Bar"]);
+    }
 }
@@ -0,0 +1,399 @@
+use oxc::allocator::Allocator;
+use oxc::ast::ast::{Comment, Statement};
+use oxc::parser::Parser as OxcParser;
+use oxc::span::SourceType;
+
+use crate::line_index::LineIndex;
+use crate::shared_lib::{AstNodeVariant, DeclarationChecker, FoundDeclarationNode};
+use crate::type_checker::TypeChecker;
+use crate::usage::{collect_usages, Usage};
+
+/// A `@tag {type} name` inside a doc comment, with byte offsets into the file.
+#[derive(Debug)]
+struct Tag<'c> {
+    name: &'c str,
+    /// Offset of the `@`.
+    start: usize,
+    /// The text between the braces, with its offset.
+    type_expression: Option<(&'c str, usize)>,
+    /// The name after the type, without `[...]` or a default value.
+    target: Option<&'c str>,
+    /// Written as `[name]` or `[name=default]`.
+    optional: bool,
+}
+
+/// Collects `@typedef` and `@callback` declarations from the doc comments of a
+/// JavaScript file, along with every type named in a JSDoc type expression.
+pub fn collect_jsdoc(
+    comments: &[Comment],
+    code: &str,
+    lines: &LineIndex,
+    filename: &str,
+) -> (Vec<FoundDeclarationNode>, Vec<Usage>) {
+    let mut declarations = Vec::new();
+    let mut usages = Vec::new();
+
+    for comment in comments {
+        let (start, end) = (comment.span.start as usize, comment.span.end as usize);
+        // `/**/` is a plain empty comment, too short to hold `/**` and `*/`.
+        if end - start < 5 || !code[start..end].starts_with("/**") {
+            continue;
+        }
+        let tags = tags(code, start + 3, end - 2);
+
+        for tag in &tags {
+            if let Some((expression, offset)) = tag.type_expression {
+                usages.extend(expression_usages(lines, expression, offset, filename));
+            }
+        }
+
+        // A declaration runs from its tag to the next `@typedef`/`@callback`.
+        let mut declaration_tags = tags
+            .iter()
+            .enumerate()
+            .filter(|(_, tag)| tag.name == "typedef" || tag.name == "callback");
+        let mut current = declaration_tags.next();
+        while let Some((index, tag)) = current {
+            current = declaration_tags.next();
+            let following = &tags[index + 1..current.map_or(tags.len(), |(next, _)| next)];
+            let span_end = current.map_or(end - 2, |(_, next)| next.start);
+            let span_end = start
+                + code[start..span_end]
+                    .trim_end_matches([' ', '\t', '\n', '\r', '*'])
+                    .len();
+
+            if let Some(node) = declaration(lines, filename, tag, following, span_end) {
+                declarations.push(node);
+            }
+        }
+    }
+    (declarations, usages)
+}
+
+/// The TypeScript equivalent of a `@typedef`/`@callback` and its member tags,
+/// checked like any other type alias.
+fn declaration(
+    lines: &LineIndex,
+    filename: &str,
+    tag: &Tag,
+    members: &[Tag],
+    span_end: usize,
+) -> Option<FoundDeclarationNode> {
+    let name = tag.target?;
+    let alias = if tag.name == "callback" {
+        let params: Vec<String> = members
+            .iter()
+            .filter(|member| matches!(member.name, "param" | "arg" | "argument"))
+            .filter_map(|member| {
+                let param = member.target?;
+                let (ty, optional) = member_type(member);
+                Some(match ty.strip_prefix("...") {
+                    Some(rest) => format!("...{}: {}[]", param, to_ts_type(rest)),
+                    None => format!("{}{}: {}", param, if optional { "?" } else { "" }, ty),
+                })
+            })
+            .collect();
+        let returns = members
+            .iter()
+            .find(|member| matches!(member.name, "returns" | "return"))
+            .and_then(|member| member.type_expression)
+            .map_or("void".to_string(), |(ty, _)| to_ts_type(ty));
+        format!("({}) => {}", params.join(", "), returns)
+    } else {
+        let properties: Vec<String> = members
+            .iter()
+            .filter(|member| matches!(member.name, "property" | "prop"))
+            .filter_map(|member| {
+                let property = member.target?;
+                // Nested `parent.child` properties aren't expanded.
+                if property.contains('.') {
+                    return None;
+                }
+                let (ty, optional) = member_type(member);
+                Some(format!(
+                    "{}{}: {}",
+                    property,
+                    if optional { "?" } else { "" },
+                    ty
+                ))
+            })
+            .collect();
+        let base = tag.type_expression.map(|(ty, _)| ty.trim());
+        match base {
+            Some("Object" | "object") | None if !properties.is_empty() => {
+                format!("{{ {} }}", properties.join("; "))
+            }
+            Some(ty) => to_ts_type(ty),
+            None => return None,
+        }
+    };
+
+    let snippet = format!("type {} = {};", name, alias);
+    let allocator = Allocator::default();
+    let parsed = OxcParser::new(&allocator, &snippet, SourceType::ts()).parse();
+    if !parsed.errors.is_empty() {
+        return None;
+    }
+    let Some(Statement::TSTypeAliasDeclaration(type_alias)) = parsed.program.body.first() else {
+        return None;
+    };
+
+    let mut node = TypeChecker { type_alias }.from_ast(&snippet, filename, false, None);
+    let (line, col) = lines.line_col(tag.start);
+    node.ast_node_variant = AstNodeVariant::Typedef;
+    node.line = line;
+    node.col = col;
+    node.span_start = tag.start;
+    node.span_end = span_end;
+    Some(node)
+}
+
+/// A member's type in TypeScript syntax, and whether it is optional either by
+/// `[name]` or a trailing `=` in the type.
+fn member_type(member: &Tag) -> (String, bool) {
+    let raw = member.type_expression.map_or("any", |(ty, _)| ty.trim());
+    match raw.strip_suffix('=') {
+        Some(ty) => (to_ts_type(ty), true),
+        None if raw.starts_with("...") => (raw.to_string(), member.optional),
+        None => (to_ts_type(raw), member.optional),
+    }
+}
+
+/// Translates the Closure-style parts of a JSDoc type: `*`, `?T`, `!T` and
+/// `Array.<T>` / `Object.<K, V>`.
+fn to_ts_type(jsdoc: &str) -> String {
+    let ty = jsdoc.trim();
+    if ty == "*" || ty == "?" {
+        return "any".to_string();
+    }
+    if let Some(nullable) = ty.strip_prefix('?') {
+        return format!("{} | null", to_ts_type(nullable));
+    }
+    if let Some(non_null) = ty.strip_prefix('!') {
+        return to_ts_type(non_null);
+    }
+    ty.replace("Object.<", "Record<").replace(".<", "<")
+}
+
+/// Usages for every type named in `expression`, located at the matching
+/// occurrence of the name in the comment.
+fn expression_usages(
+    lines: &LineIndex,
+    expression: &str,
+    offset: usize,
+    filename: &str,
+) -> Vec<Usage> {
+    let snippet = format!(
+        "type __jsdoc = {};",
+        to_ts_type(expression.trim_end_matches('='))
+    );
+    let allocator = Allocator::default();
+    let parsed = OxcParser::new(&allocator, &snippet, SourceType::ts()).parse();
+    if !parsed.errors.is_empty() {
+        return Vec::new();
+    }
+
+    let mut seen: Vec<&str> = Vec::new();
+    let mut usages = Vec::new();
+    let found = collect_usages(&parsed.program, &LineIndex::new(&snippet), filename);
+    for usage in &found {
+        let nth = seen.iter().filter(|name| **name == usage.name).count();
+        seen.push(&usage.name);
+        let Some(at) = nth_word(expression, &usage.name, nth) else {
+            continue;
+        };
+        let start = offset + at;
+        let (line, col) = lines.line_col(start);
+        usages.push(Usage {
+            line,
+            col,
+            span_start: start,
+            span_end: start + usage.name.len(),
+            ..usage.clone()
+        });
+    }
+    usages
+}
+
+/// Offset of the `nth` whole-word occurrence of `word` in `text`.
+fn nth_word(text: &str, word: &str, nth: usize) -> Option<usize> {
+    let is_word = |ch: char| ch.is_alphanumeric() || ch == '_' || ch == '$';
+    text.match_indices(word)
+        .map(|(at, _)| at)
+        .filter(|&at| {
+            !text[..at].chars().next_back().is_some_and(is_word)
+                && !text[at + word.len()..].chars().next().is_some_and(is_word)
+        })
+        .nth(nth)
+}
+
+/// The block tags of the doc comment content between `start` and `end`.
+fn tags(code: &str, start: usize, end: usize) -> Vec<Tag<'_>> {
+    let content = &code[start..end];
+    let mut tags = Vec::new();
+    let mut search = 0;
+
+    while let Some(found) = content[search..].find('@') {
+        let at = search + found;
+        search = at + 1;
+        // Block tags start a line (after the `*` gutter); `@` elsewhere is text.
+        let line_start = content[..at].rfind('\n').map_or(0, |i| i + 1);
+        let gutter = content[line_start..at].trim();
+        if !(gutter.is_empty() || gutter == "*") {
+            continue;
+        }
+
+        let name_end = content[at + 1..]
+            .find(|ch: char| !ch.is_alphanumeric())
+            .map_or(content.len(), |i| at + 1 + i);
+        let name = &content[at + 1..name_end];
+        let mut rest = name_end;
+        skip_whitespace(content, &mut rest);
+
+        let mut type_expression = None;
+        if content[rest..].starts_with('{') {
+            if let Some(close) = matching_brace(&content[rest..]) {
+                type_expression = Some((&content[rest + 1..rest + close], start + rest + 1));
+                rest += close + 1;
+                skip_whitespace(content, &mut rest);
+            }
+        }
+
+        let (target, optional) = target(&content[rest..]);
+        tags.push(Tag {
+            name,
+            start: start + at,
+            type_expression,
+            target,
+            optional,
+        });
+    }
+    tags
+}
+
+fn skip_whitespace(content: &str, at: &mut usize) {
+    let trimmed = content[*at..].trim_start_matches([' ', '\t']);
+    *at = content.len() - trimmed.len();
+}
+
+/// The offset of the `}` closing the `{` `text` starts with.
+fn matching_brace(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, ch) in text.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The name a tag applies to: `name`, `[name]` or `[name=default]`.
+fn target(text: &str) -> (Option<&str>, bool) {
+    let is_name = |ch: char| ch.is_alphanumeric() || matches!(ch, '_' | '$' | '.');
+    if let Some(inner) = text.strip_prefix('[') {
+        let end = inner.find(|ch: char| !is_name(ch)).unwrap_or(inner.len());
+        return ((end > 0).then(|| &inner[..end]), true);
+    }
+    let end = text.find(|ch: char| !is_name(ch)).unwrap_or(text.len());
+    ((end > 0).then(|| &text[..end]), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_source;
+    use crate::shared_lib::AstNodeVariant;
+
+    #[test]
+    fn test_typedef_forms_match_typescript_bodies() {
+        let parsed = parse_source(
+            r#"
+/**
+ * @typedef {{ id: string, tags: Array.<string> }} User
+ */
+
+/**
+ * A point.
+ * @typedef {Object} Point
+ * @property {number} x
+ * @property {number} [y]
+ */
+
+/**
+ * @callback Handler
+ * @param {User} user
+ * @param {number=} retries
+ * @returns {boolean}
+ */
+"#,
+            "models.js",
+            &[],
+        );
+
+        let bodies: Vec<(&str, &str)> = parsed
+            .declarations
+            .iter()
+            .map(|node| (node.name.as_str(), node.body.as_str()))
+            .collect();
+        assert_eq!(
+            bodies,
+            vec![
+                ("User", "{ id: string; tags: Array<string> }"),
+                ("Point", "{ x: number; y?: number }"),
+                ("Handler", "(user: User, retries?: number) => boolean"),
+            ]
+        );
+        assert!(parsed
+            .declarations
+            .iter()
+            .all(|node| node.ast_node_variant == AstNodeVariant::Typedef));
+        assert_eq!(parsed.declarations[1].line, 8);
+    }
+
+    #[test]
+    fn test_empty_comments_are_skipped() {
+        let parsed = parse_source("/**/\n/***/\nconst x = 1;", "a.js", &[]);
+        assert!(parsed.declarations.is_empty());
+        assert!(parsed.errors.is_empty());
+    }
+
+    #[test]
+    fn test_typedef_duplicates_typescript_interface() {
+        let mut analyzer = crate::Analyzer::new();
+        analyzer.add_source(
+            "legacy/user.js",
+            "/**\n * @typedef {Object} User\n * @property {string} id\n */",
+        );
+        analyzer.add_source("src/user.ts", "export interface User { id: string }");
+
+        let findings = analyzer.findings();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, "declaration");
+        assert_eq!(findings[0].severity, crate::Severity::Critical);
+    }
+
+    #[test]
+    fn test_jsdoc_type_references_are_usages() {
+        let parsed = parse_source(
+            "/** @type {Map<User, User>} */\nconst users = new Map();\n/** @param {Point} p */\nfunction f(p) {}",
+            "app.js",
+            &[],
+        );
+
+        let names: Vec<(&str, usize)> = parsed
+            .usages
+            .iter()
+            .map(|usage| (usage.name.as_str(), usage.span_start))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("Map", 11), ("User", 15), ("User", 21), ("Point", 68)]
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::analyzer::Finding;
+use crate::shared_lib::FoundDeclarationNode;
+
+/// How many entries the "largest" and "most duplicated" lists keep.
+const TOP: usize = 10;
+
+/// Declaration counts of one kind.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct VariantTotals {
+    pub total: usize,
+    pub exported: usize,
+    pub local: usize,
+}
+
+/// One of the largest declarations, measured by its span in the source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeclarationSize {
+    pub name: String,
+    pub kind: String,
+    pub filename: String,
+    pub line: usize,
+    pub bytes: usize,
+}
+
+/// A name declared more than once, with how many copies the findings involve.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DuplicatedName {
+    pub name: String,
+    pub copies: usize,
+    pub files: usize,
+}
+
+/// How much of a directory's or project's declarations are duplicated.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GroupRatio {
+    pub group: String,
+    pub declarations: usize,
+    pub duplicated: usize,
+    /// `duplicated / declarations`, between 0 and 1.
+    pub ratio: f64,
+}
+
+/// An overview of the declarations a scan found, for trending type hygiene
+/// over time.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Landscape {
+    pub declarations: usize,
+    /// Keyed by [`AstNodeVariant::label`](crate::AstNodeVariant::label).
+    pub variants: BTreeMap<String, VariantTotals>,
+    pub largest: Vec<DeclarationSize>,
+    pub most_duplicated: Vec<DuplicatedName>,
+    /// Ordered by ratio, highest first.
+    pub groups: Vec<GroupRatio>,
+}
+
+impl Landscape {
+    /// Summarises `results`, counting a declaration as duplicated when one of
+    /// `findings` involves it. `group_of` names the directory or project a
+    /// file belongs to, e.g. [`directory_of`].
+    pub fn new(
+        results: &HashMap<String, Vec<FoundDeclarationNode>>,
+        findings: &[Finding],
+        group_of: impl Fn(&str) -> String,
+    ) -> Landscape {
+        let mut duplicated: HashMap<&str, HashSet<(&str, usize)>> = HashMap::new();
+        for finding in findings {
+            let copies = duplicated.entry(finding.name.as_str()).or_default();
+            for node in [&finding.first, &finding.second] {
+                copies.insert((node.filename.as_str(), node.span_start));
+            }
+        }
+
+        let mut landscape = Landscape::default();
+        let mut largest = Vec::new();
+        let mut groups: HashMap<String, (usize, usize)> = HashMap::new();
+        for (name, nodes) in results {
+            for node in nodes {
+                landscape.declarations += 1;
+                let totals = landscape
+                    .variants
+                    .entry(node.ast_node_variant.label().to_string())
+                    .or_default();
+                totals.total += 1;
+                if node.is_exported {
+                    totals.exported += 1;
+                } else {
+                    totals.local += 1;
+                }
+
+                largest.push(DeclarationSize {
+                    name: name.clone(),
+                    kind: node.ast_node_variant.label().to_string(),
+                    filename: node.filename.clone(),
+                    line: node.line,
+                    bytes: node.span_end - node.span_start,
+                });
+
+                let group = groups.entry(group_of(&node.filename)).or_default();
+                group.0 += 1;
+                if duplicated.get(name.as_str()).is_some_and(|copies| {
+                    copies.contains(&(node.filename.as_str(), node.span_start))
+                }) {
+                    group.1 += 1;
+                }
+            }
+        }
+
+        largest.sort_by(|a, b| {
+            b.bytes
+                .cmp(&a.bytes)
+                .then_with(|| (&a.filename, a.line).cmp(&(&b.filename, b.line)))
+        });
+        largest.truncate(TOP);
+        landscape.largest = largest;
+
+        let mut most_duplicated: Vec<DuplicatedName> = duplicated
+            .into_iter()
+            .map(|(name, copies)| DuplicatedName {
+                name: name.to_string(),
+                copies: copies.len(),
+                files: copies
+                    .iter()
+                    .map(|(filename, _)| filename)
+                    .collect::<HashSet<_>>()
+                    .len(),
+            })
+            .collect();
+        most_duplicated.sort_by(|a, b| b.copies.cmp(&a.copies).then_with(|| a.name.cmp(&b.name)));
+        most_duplicated.truncate(TOP);
+        landscape.most_duplicated = most_duplicated;
+
+        let mut groups: Vec<GroupRatio> = groups
+            .into_iter()
+            .map(|(group, (declarations, duplicated))| GroupRatio {
+                group,
+                declarations,
+                duplicated,
+                ratio: duplicated as f64 / declarations as f64,
+            })
+            .collect();
+        groups.sort_by(|a, b| {
+            b.ratio
+                .total_cmp(&a.ratio)
+                .then_with(|| b.declarations.cmp(&a.declarations))
+                .then_with(|| a.group.cmp(&b.group))
+        });
+        landscape.groups = groups;
+
+        landscape
+    }
+}
+
+/// The directory `filename` is in, or `.` for files at the top.
+pub fn directory_of(filename: &str) -> String {
+    match Path::new(filename).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_string_lossy().to_string(),
+        _ => ".".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Analyzer;
+
+    #[test]
+    fn test_landscape_counts() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source(
+            "api/user.ts",
+            "export interface User {\n  id: string;\n  name: string;\n}\ntype Id = string;",
+        );
+        analyzer.add_source("api/order.ts", "export type Id = string;");
+        analyzer.add_source("ui/user.ts", "interface User { id: string }");
+        let landscape = Landscape::new(analyzer.declarations(), &analyzer.findings(), directory_of);
+
+        assert_eq!(landscape.declarations, 4);
+        assert_eq!(
+            landscape.variants["type"],
+            VariantTotals {
+                total: 2,
+                exported: 1,
+                local: 1
+            }
+        );
+        assert_eq!(landscape.largest[0].name, "User");
+        assert_eq!(landscape.largest[0].filename, "api/user.ts");
+
+        let names: Vec<(&str, usize)> = landscape
+            .most_duplicated
+            .iter()
+            .map(|name| (name.name.as_str(), name.copies))
+            .collect();
+        assert_eq!(names, vec![("Id", 2), ("User", 2)]);
+
+        let groups: Vec<(&str, usize, usize)> = landscape
+            .groups
+            .iter()
+            .map(|group| (group.group.as_str(), group.declarations, group.duplicated))
+            .collect();
+        assert_eq!(groups, vec![("api", 3, 3), ("ui", 1, 1)]);
+    }
+}
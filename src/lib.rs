@@ -0,0 +1,34 @@
+//! TTA - TypeScript Type Analysis
+//!
+//! Finds type aliases and interfaces that are declared more than once across a
+//! TypeScript code base. The `tta` binary is a thin wrapper around [`Analyzer`].
+
+pub mod analyzer;
+pub mod component;
+pub mod consolidate;
+pub mod deep;
+pub mod docs;
+pub mod files;
+pub mod git;
+pub mod imports;
+pub mod index;
+pub mod interface_checker;
+pub mod jsdoc;
+pub mod landscape;
+pub mod line_index;
+pub mod member_diff;
+pub mod parser;
+pub mod rename;
+pub mod shared_lib;
+pub mod tsconfig;
+pub mod type_checker;
+pub mod unused;
+pub mod usage;
+pub mod workspace;
+
+#[cfg(test)]
+mod test_support;
+
+pub use analyzer::{find_duplicates, Analyzer, Finding, Severity};
+pub use parser::{parse_source, ParsedFile};
+pub use shared_lib::{AstNodeVariant, CheckerFactory, DeclarationChecker, FoundDeclarationNode};
@@ -0,0 +1,114 @@
+/// Line starts of a source, so byte offsets map to lines and columns by a
+/// binary search instead of a walk from the start of the file.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'s> {
+    source: &'s str,
+    /// Byte offset of the first character of every line.
+    line_starts: Vec<usize>,
+}
+
+impl<'s> LineIndex<'s> {
+    pub fn new(source: &'s str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            source
+                .bytes()
+                .enumerate()
+                .filter(|(_, byte)| *byte == b'\n')
+                .map(|(i, _)| i + 1),
+        );
+        LineIndex {
+            source,
+            line_starts,
+        }
+    }
+
+    /// 1-based line and column, counting characters. Offsets past the end
+    /// clamp to it.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let (line, start, offset) = self.locate(offset);
+        let prefix = &self.source[start..offset];
+        let col = if prefix.is_ascii() {
+            prefix.len()
+        } else {
+            prefix.chars().count()
+        };
+        (line + 1, col + 1)
+    }
+
+    /// 0-based line and column in UTF-16 code units, as LSP and SARIF count them.
+    pub fn line_col_utf16(&self, offset: usize) -> (u32, u32) {
+        let (line, start, offset) = self.locate(offset);
+        let col: usize = self.source[start..offset]
+            .chars()
+            .map(char::len_utf16)
+            .sum();
+        (line as u32, col as u32)
+    }
+
+    /// The byte offset of a 0-based line and UTF-16 column, clamped to the end
+    /// of that line (or of the source).
+    pub fn offset_utf16(&self, line: u32, col: u32) -> usize {
+        let Some(&start) = self.line_starts.get(line as usize) else {
+            return self.source.len();
+        };
+        let mut remaining = col as usize;
+        for (i, ch) in self.source[start..].char_indices() {
+            if remaining == 0 || ch == '\n' {
+                return start + i;
+            }
+            remaining = remaining.saturating_sub(ch.len_utf16());
+        }
+        self.source.len()
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// `(0-based line, its start, offset)` with the offset clamped into the
+    /// source and onto a character boundary.
+    fn locate(&self, offset: usize) -> (usize, usize, usize) {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        (line, self.line_starts[line], offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared_lib::byte_offset_to_line_col;
+
+    #[test]
+    fn test_line_col_matches_walk() {
+        let source = "type A = 'é';\n\ninterface B {\r\n  x: '😀'; y: string }\n";
+        let index = LineIndex::new(source);
+        for (offset, _) in source.char_indices().chain([(source.len(), ' ')]) {
+            assert_eq!(
+                index.line_col(offset),
+                byte_offset_to_line_col(source, offset),
+                "offset {}",
+                offset
+            );
+        }
+        assert_eq!(index.line_count(), 5);
+    }
+
+    #[test]
+    fn test_utf16_columns_round_trip() {
+        let source = "type A = '😀';\ntype B = string;";
+        let index = LineIndex::new(source);
+        let quote = source.rfind('\'').unwrap();
+        assert_eq!(index.line_col_utf16(quote), (0, 12));
+        assert_eq!(index.line_col_utf16(source.find("B").unwrap()), (1, 5));
+        for offset in [0, quote, source.find("B").unwrap(), source.len()] {
+            let (line, col) = index.line_col_utf16(offset);
+            assert_eq!(index.offset_utf16(line, col), offset);
+        }
+        assert_eq!(index.offset_utf16(7, 0), source.len());
+    }
+}
@@ -0,0 +1,637 @@
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{CodeActionRequest, HoverRequest, Request as _};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity,
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DidSaveTextDocumentParams, Hover, HoverContents, HoverParams, HoverProviderCapability,
+    InitializeParams, Location, MarkupContent, MarkupKind, Position, PublishDiagnosticsParams,
+    Range, SaveOptions, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TextDocumentSyncOptions, TextDocumentSyncSaveOptions, TextEdit, Url, WorkspaceEdit,
+};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use tta::deep::{apply_deep_comparison, DeepComparer};
+use tta::files::{find_ts_files, is_component_file, is_ts_file, read_source};
+use tta::line_index::LineIndex;
+use tta::{Analyzer, Finding, FoundDeclarationNode, Severity};
+
+type LspResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
+
+/// Runs a language server over stdio that publishes duplicate declaration
+/// diagnostics for the workspace, deep-compared with `deep`.
+pub fn run(deep: bool) -> LspResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(
+            TextDocumentSyncOptions {
+                open_close: Some(true),
+                change: Some(TextDocumentSyncKind::FULL),
+                save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
+                    include_text: Some(true),
+                })),
+                ..Default::default()
+            },
+        )),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        ..Default::default()
+    };
+    let params = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+
+    let mut server = Server {
+        deep,
+        ..Server::default()
+    };
+    if let Some(root) = workspace_root(&params) {
+        server.scan(&root);
+    }
+    server.publish_diagnostics(&connection)?;
+
+    for msg in &connection.receiver {
+        match msg {
+            Message::Request(req) => {
+                if connection.handle_shutdown(&req)? {
+                    break;
+                }
+                server.handle_request(&connection, req)?;
+            }
+            Message::Notification(not) => server.handle_notification(&connection, not)?,
+            Message::Response(_) => {}
+        }
+    }
+
+    io_threads.join()?;
+    Ok(())
+}
+
+#[allow(deprecated)]
+fn workspace_root(params: &InitializeParams) -> Option<PathBuf> {
+    params
+        .workspace_folders
+        .as_ref()
+        .and_then(|folders| folders.first())
+        .map(|folder| &folder.uri)
+        .or(params.root_uri.as_ref())
+        .and_then(|uri| uri.to_file_path().ok())
+        .or_else(|| params.root_path.as_ref().map(PathBuf::from))
+}
+
+#[derive(Default)]
+struct Server {
+    analyzer: Analyzer,
+    /// Compare bodies structurally, as `--deep` does.
+    deep: bool,
+    /// Current text of every indexed file; open buffers shadow the file on disk.
+    sources: HashMap<String, String>,
+    /// The diagnostics last sent for every file that has any, so unchanged
+    /// files aren't republished and stale ones can be cleared.
+    published: HashMap<String, Vec<Diagnostic>>,
+}
+
+impl Server {
+    fn scan(&mut self, root: &Path) {
+        for path in find_ts_files(root) {
+            if let Ok(code) = read_source(Path::new(&path)) {
+                self.update(&path, code);
+            }
+        }
+    }
+
+    fn update(&mut self, filename: &str, code: String) {
+        self.analyzer.add_source(filename, &code);
+        self.sources.insert(filename.to_string(), code);
+    }
+
+    fn handle_notification(&mut self, connection: &Connection, not: Notification) -> LspResult<()> {
+        match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidOpenTextDocumentParams>(not) else {
+                    return Ok(());
+                };
+                if let Some(filename) = uri_to_filename(&params.text_document.uri) {
+                    self.update(&filename, params.text_document.text);
+                }
+            }
+            DidChangeTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidChangeTextDocumentParams>(not) else {
+                    return Ok(());
+                };
+                let Some(filename) = uri_to_filename(&params.text_document.uri) else {
+                    return Ok(());
+                };
+                // Full sync: the last change holds the whole document.
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.update(&filename, change.text);
+                }
+            }
+            DidSaveTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidSaveTextDocumentParams>(not) else {
+                    return Ok(());
+                };
+                let Some(filename) = uri_to_filename(&params.text_document.uri) else {
+                    return Ok(());
+                };
+                if let Some(text) = params.text {
+                    self.update(&filename, text);
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidCloseTextDocumentParams>(not) else {
+                    return Ok(());
+                };
+                let Some(filename) = uri_to_filename(&params.text_document.uri) else {
+                    return Ok(());
+                };
+                // Unsaved edits are gone once the buffer closes; fall back to disk.
+                match read_source(Path::new(&filename)) {
+                    Ok(code) => self.update(&filename, code),
+                    Err(_) => {
+                        self.analyzer.remove_source(&filename);
+                        self.sources.remove(&filename);
+                    }
+                }
+            }
+            _ => return Ok(()),
+        }
+
+        self.publish_diagnostics(connection)
+    }
+
+    fn handle_request(&mut self, connection: &Connection, req: Request) -> LspResult<()> {
+        let response = match req.method.as_str() {
+            HoverRequest::METHOD => match extract::<HoverParams>(req) {
+                Ok((id, params)) => {
+                    let position = params.text_document_position_params;
+                    let hover = uri_to_filename(&position.text_document.uri)
+                        .and_then(|filename| self.hover(&filename, position.position));
+                    Response::new_ok(id, hover)
+                }
+                Err(response) => response,
+            },
+            CodeActionRequest::METHOD => match extract::<CodeActionParams>(req) {
+                Ok((id, params)) => {
+                    let actions = uri_to_filename(&params.text_document.uri)
+                        .map(|filename| self.code_actions(&filename, &params))
+                        .unwrap_or_default();
+                    Response::new_ok(id, actions)
+                }
+                Err(response) => response,
+            },
+            _ => Response::new_err(
+                req.id,
+                lsp_server::ErrorCode::MethodNotFound as i32,
+                format!("Unhandled method: {}", req.method),
+            ),
+        };
+
+        connection.sender.send(Message::Response(response))?;
+        Ok(())
+    }
+
+    /// The same findings the CLI reports, deep-compared when asked to.
+    fn findings(&self) -> Vec<Finding> {
+        let imports = self.analyzer.import_graph();
+        let mut findings = self.analyzer.findings_with_imports(&imports);
+        if self.deep {
+            let comparer = DeepComparer::new(self.analyzer.declarations(), Some(&imports));
+            apply_deep_comparison(&mut findings, &comparer);
+        }
+        findings
+    }
+
+    /// Sends the diagnostics of every file whose set changed since the last
+    /// time, clearing files that no longer have any.
+    fn publish_diagnostics(&mut self, connection: &Connection) -> LspResult<()> {
+        let diagnostics = collect_diagnostics(&self.findings(), &self.sources);
+
+        let stale: Vec<String> = self
+            .published
+            .keys()
+            .filter(|filename| !diagnostics.contains_key(*filename))
+            .cloned()
+            .collect();
+        for filename in stale {
+            send_diagnostics(connection, &filename, Vec::new())?;
+        }
+        for (filename, file_diagnostics) in &diagnostics {
+            if self.published.get(filename) != Some(file_diagnostics) {
+                send_diagnostics(connection, filename, file_diagnostics.clone())?;
+            }
+        }
+
+        self.published = diagnostics;
+        Ok(())
+    }
+
+    fn hover(&self, filename: &str, position: Position) -> Option<Hover> {
+        let lines = LineIndex::new(self.sources.get(filename)?);
+        let offset = position_to_offset(&lines, position);
+        let node = self.declaration_at(filename, offset)?;
+
+        let occurrences = self
+            .analyzer
+            .declarations()
+            .get(&node.name)
+            .map_or(0, Vec::len);
+        let mut value = format!(
+            "```typescript\n{} {} {}\n```",
+            node.ast_node_variant.label(),
+            node.name,
+            node.body
+        );
+        if occurrences > 1 {
+            value.push_str(&format!(
+                "\n\nDeclared {} times in the workspace.",
+                occurrences
+            ));
+        }
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(node_range(&lines, node)),
+        })
+    }
+
+    /// Offers to delete a declaration that repeats an identical one earlier in
+    /// the same file, which is the only removal that can't break other modules.
+    fn code_actions(&self, filename: &str, params: &CodeActionParams) -> Vec<CodeActionOrCommand> {
+        let Some(source) = self.sources.get(filename) else {
+            return Vec::new();
+        };
+        let lines = LineIndex::new(source);
+        let start = position_to_offset(&lines, params.range.start);
+        let end = position_to_offset(&lines, params.range.end);
+
+        let mut actions = Vec::new();
+        for nodes in self.analyzer.declarations().values() {
+            for (i, node) in nodes.iter().enumerate() {
+                if node.filename != filename || node.span_end < start || node.span_start > end {
+                    continue;
+                }
+                let Some(original) = nodes[..i]
+                    .iter()
+                    .find(|other| other.filename == filename && other.body == node.body)
+                else {
+                    continue;
+                };
+
+                let Ok(uri) = Url::from_file_path(filename) else {
+                    continue;
+                };
+                let mut delete_end = node.span_end;
+                if source[delete_end..].starts_with('\n') {
+                    delete_end += 1;
+                }
+                let edit = TextEdit {
+                    range: Range {
+                        start: offset_to_position(&lines, node.span_start),
+                        end: offset_to_position(&lines, delete_end),
+                    },
+                    new_text: String::new(),
+                };
+
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!(
+                        "Remove duplicate {} '{}' (identical to line {})",
+                        node.ast_node_variant.label(),
+                        node.name,
+                        original.line
+                    ),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(uri, vec![edit])])),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }));
+            }
+        }
+        actions
+    }
+
+    fn declaration_at(&self, filename: &str, offset: usize) -> Option<&FoundDeclarationNode> {
+        self.analyzer
+            .declarations()
+            .values()
+            .flatten()
+            .find(|node| {
+                node.filename == filename && node.span_start <= offset && offset < node.span_end
+            })
+    }
+}
+
+/// The params of `req`, or an `InvalidParams` error response to send instead,
+/// so one malformed request doesn't stop the server.
+fn extract<P: serde::de::DeserializeOwned>(req: Request) -> Result<(RequestId, P), Response> {
+    match serde_json::from_value(req.params) {
+        Ok(params) => Ok((req.id, params)),
+        Err(err) => Err(Response::new_err(
+            req.id,
+            lsp_server::ErrorCode::InvalidParams as i32,
+            format!("Invalid params for {}: {}", req.method, err),
+        )),
+    }
+}
+
+/// The params of `not`, or `None` after logging why they don't parse, so one
+/// malformed notification doesn't stop the server.
+fn notification_params<P: serde::de::DeserializeOwned>(not: Notification) -> Option<P> {
+    match serde_json::from_value(not.params) {
+        Ok(params) => Some(params),
+        Err(err) => {
+            eprintln!("Ignoring {} with invalid params: {}", not.method, err);
+            None
+        }
+    }
+}
+
+fn send_diagnostics(
+    connection: &Connection,
+    filename: &str,
+    diagnostics: Vec<Diagnostic>,
+) -> LspResult<()> {
+    let Ok(uri) = Url::from_file_path(filename) else {
+        return Ok(());
+    };
+    let params = PublishDiagnosticsParams {
+        uri,
+        diagnostics,
+        version: None,
+    };
+    connection
+        .sender
+        .send(Message::Notification(Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            params,
+        )))?;
+    Ok(())
+}
+
+fn uri_to_filename(uri: &Url) -> Option<String> {
+    let path = uri.to_file_path().ok()?;
+    if !is_ts_file(&path) && !is_component_file(&path) {
+        return None;
+    }
+    Some(path.to_string_lossy().to_string())
+}
+
+/// Builds the diagnostics for every declaration that takes part in a finding,
+/// keyed by filename. A declaration is an error when any of its findings is
+/// critical, and links to the other copy of each.
+fn collect_diagnostics(
+    findings: &[Finding],
+    sources: &HashMap<String, String>,
+) -> HashMap<String, Vec<Diagnostic>> {
+    let lines: HashMap<&str, LineIndex> = sources
+        .iter()
+        .map(|(filename, source)| (filename.as_str(), LineIndex::new(source)))
+        .collect();
+
+    // Findings per declaration, in the order they were found.
+    let mut by_node: Vec<(
+        &FoundDeclarationNode,
+        Vec<(&Finding, &FoundDeclarationNode)>,
+    )> = Vec::new();
+    let mut positions: HashMap<(&str, usize), usize> = HashMap::new();
+    for finding in findings {
+        for (node, other) in [
+            (&finding.first, &finding.second),
+            (&finding.second, &finding.first),
+        ] {
+            let key = (node.filename.as_str(), node.span_start);
+            let position = *positions.entry(key).or_insert_with(|| {
+                by_node.push((node, Vec::new()));
+                by_node.len() - 1
+            });
+            by_node[position].1.push((finding, other));
+        }
+    }
+
+    let mut diagnostics: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+    for (node, node_findings) in by_node {
+        let Some(node_lines) = lines.get(node.filename.as_str()) else {
+            continue;
+        };
+        let headline = node_findings
+            .iter()
+            .find(|(finding, _)| finding.severity == Severity::Critical)
+            .unwrap_or(&node_findings[0])
+            .0;
+
+        let related_information = node_findings
+            .iter()
+            .filter_map(|(finding, other)| {
+                let other_lines = lines.get(other.filename.as_str())?;
+                let uri = Url::from_file_path(&other.filename).ok()?;
+                Some(DiagnosticRelatedInformation {
+                    location: Location {
+                        uri,
+                        range: node_range(other_lines, other),
+                    },
+                    message: match finding.severity {
+                        Severity::Critical => "also defined here with the same body",
+                        Severity::Warning => "also defined here with a different body",
+                    }
+                    .to_string(),
+                })
+            })
+            .collect();
+
+        diagnostics
+            .entry(node.filename.clone())
+            .or_default()
+            .push(Diagnostic {
+                range: node_range(node_lines, node),
+                severity: Some(match headline.severity {
+                    Severity::Critical => DiagnosticSeverity::ERROR,
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                }),
+                source: Some("tta".to_string()),
+                message: headline.message(),
+                related_information: Some(related_information),
+                ..Default::default()
+            });
+    }
+
+    diagnostics
+}
+
+fn node_range(lines: &LineIndex, node: &FoundDeclarationNode) -> Range {
+    Range {
+        start: offset_to_position(lines, node.span_start),
+        end: offset_to_position(lines, node.span_end),
+    }
+}
+
+fn offset_to_position(lines: &LineIndex, offset: usize) -> Position {
+    let (line, character) = lines.line_col_utf16(offset);
+    Position::new(line, character)
+}
+
+fn position_to_offset(lines: &LineIndex, position: Position) -> usize {
+    lines.offset_utf16(position.line, position.character)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_to_position_counts_utf16_units() {
+        let source = "type A = '😀';\ntype B = string;";
+        let lines = LineIndex::new(source);
+        assert_eq!(offset_to_position(&lines, 0), Position::new(0, 0));
+        let b = source.find("type B").unwrap();
+        assert_eq!(offset_to_position(&lines, b), Position::new(1, 0));
+        let quote = source.rfind('\'').unwrap();
+        assert_eq!(offset_to_position(&lines, quote), Position::new(0, 12));
+    }
+
+    #[test]
+    fn test_position_to_offset_roundtrip() {
+        let source = "type A = '😀';\ntype B = string;";
+        let lines = LineIndex::new(source);
+        for offset in [0, source.rfind('\'').unwrap(), source.find("B").unwrap()] {
+            assert_eq!(
+                position_to_offset(&lines, offset_to_position(&lines, offset)),
+                offset
+            );
+        }
+    }
+
+    #[test]
+    fn test_collect_diagnostics_links_other_occurrences() {
+        let mut analyzer = Analyzer::new();
+        let mut sources = HashMap::new();
+        for (filename, code) in [
+            ("/ws/a.ts", "interface IFoo { x: string; }"),
+            ("/ws/b.ts", "interface IFoo { x: number; }"),
+            ("/ws/c.ts", "type Unique = string;"),
+        ] {
+            analyzer.add_source(filename, code);
+            sources.insert(filename.to_string(), code.to_string());
+        }
+
+        let diagnostics = collect_diagnostics(&analyzer.findings(), &sources);
+        assert!(!diagnostics.contains_key("/ws/c.ts"));
+
+        let a = &diagnostics["/ws/a.ts"];
+        assert_eq!(a.len(), 1);
+        assert_eq!(a[0].severity, Some(DiagnosticSeverity::WARNING));
+        let related = a[0].related_information.as_ref().unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].location.uri.path(), "/ws/b.ts");
+    }
+
+    #[test]
+    fn test_collect_diagnostics_uses_findings() {
+        let mut analyzer = Analyzer::new();
+        let mut sources = HashMap::new();
+        for (filename, code) in [
+            ("/ws/a.ts", "interface IFoo { x: string; }"),
+            ("/ws/b.ts", "interface IFoo { x: string; }"),
+            ("/ws/c.ts", "interface IFoo { y: string; }"),
+        ] {
+            analyzer.add_source(filename, code);
+            sources.insert(filename.to_string(), code.to_string());
+        }
+
+        let findings = analyzer.findings();
+        let diagnostics = collect_diagnostics(&findings, &sources);
+        let a = &diagnostics["/ws/a.ts"];
+        assert_eq!(a.len(), 1);
+        assert_eq!(a[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(
+            a[0].message,
+            "Duplicate interface 'IFoo' with identical body"
+        );
+        assert_eq!(a[0].related_information.as_ref().unwrap().len(), 2);
+        let c = &diagnostics["/ws/c.ts"];
+        assert_eq!(c[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(
+            c[0].message,
+            "Duplicate interface name 'IFoo' with different body"
+        );
+    }
+
+    #[test]
+    fn test_malformed_request_gets_an_error_response() {
+        let (server_side, client_side) = Connection::memory();
+        let mut server = Server::default();
+        let request = Request::new(
+            RequestId::from(1),
+            HoverRequest::METHOD.to_string(),
+            serde_json::json!({ "position": "nowhere" }),
+        );
+
+        server.handle_request(&server_side, request).unwrap();
+        let Message::Response(response) = client_side.receiver.recv().unwrap() else {
+            panic!("expected a response");
+        };
+        assert_eq!(
+            response.error.unwrap().code,
+            lsp_server::ErrorCode::InvalidParams as i32
+        );
+    }
+
+    #[test]
+    fn test_malformed_notification_is_ignored() {
+        let (server_side, client_side) = Connection::memory();
+        let mut server = Server::default();
+        let notification = Notification::new(
+            DidOpenTextDocument::METHOD.to_string(),
+            serde_json::json!({ "textDocument": "nowhere" }),
+        );
+
+        assert!(server
+            .handle_notification(&server_side, notification)
+            .is_ok());
+        assert!(client_side.receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_only_changed_diagnostics_are_republished() {
+        let (server_side, client_side) = Connection::memory();
+        let mut server = Server::default();
+        server.update("/ws/a.ts", "interface IFoo { x: string; }".to_string());
+        server.update("/ws/b.ts", "interface IFoo { x: string; }".to_string());
+        server.update("/ws/c.ts", "type Bar = string;".to_string());
+        server.publish_diagnostics(&server_side).unwrap();
+        let published: Vec<Message> = client_side.receiver.try_iter().collect();
+        assert_eq!(published.len(), 2);
+
+        server.update("/ws/c.ts", "type Bar = number;".to_string());
+        server.publish_diagnostics(&server_side).unwrap();
+        assert!(client_side.receiver.try_recv().is_err());
+
+        server.update("/ws/b.ts", "interface IFoo { x: number; }".to_string());
+        server.publish_diagnostics(&server_side).unwrap();
+        assert_eq!(client_side.receiver.try_iter().count(), 2);
+
+        server.update("/ws/b.ts", "interface IBar { x: number; }".to_string());
+        server.publish_diagnostics(&server_side).unwrap();
+        let cleared: Vec<PublishDiagnosticsParams> = client_side
+            .receiver
+            .try_iter()
+            .map(|message| match message {
+                Message::Notification(not) => serde_json::from_value(not.params).unwrap(),
+                _ => panic!("expected a notification"),
+            })
+            .collect();
+        assert_eq!(cleared.len(), 2);
+        assert!(cleared.iter().all(|params| params.diagnostics.is_empty()));
+        assert!(server.published.is_empty());
+    }
+}
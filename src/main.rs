@@ -1,176 +1,521 @@
-pub mod interface_checker;
-pub mod shared_lib;
-pub mod type_checker;
-
-use ariadne::{Cache, Color, Label, Report, ReportKind, Source};
-use clap::Parser;
-use ignore::WalkBuilder;
+mod fix;
+mod html;
+mod lsp;
+mod markdown;
+mod report;
+mod stats;
+mod watch;
+
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
 use indicatif::{ProgressBar, ProgressStyle};
-use oxc::allocator::Allocator;
-use oxc::ast::ast::{Declaration, Statement, TSTypeName};
-use oxc::parser::Parser as OxcParser;
-use oxc::span::SourceType;
-use std::collections::HashMap;
-use std::fmt;
-use std::path::Path;
-
-use crate::interface_checker::InterfaceChecker;
-use crate::shared_lib::{DeclarationChecker, FoundDeclarationNode};
-use crate::type_checker::TypeChecker;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tta::deep::{apply_deep_comparison, DeepComparer};
+use tta::files::{
+    find_doc_files, find_source_files, is_doc_file, is_generated_declaration, is_source_file,
+    read_source,
+};
+use tta::git::{changed_files, retain_changed_groups, ChangeSet};
+use tta::imports::{ImportGraph, ModuleResolver};
+use tta::index::DeclarationIndex;
+use tta::landscape::{directory_of, Landscape};
+use tta::parser::ParseError;
+use tta::tsconfig::{resolve_projects, TsConfig, TsProject};
+use tta::workspace::{retain_project, Workspace};
+use tta::{Analyzer, Finding, Severity};
+
+use crate::html::render_html;
+use crate::markdown::render_markdown;
+use crate::report::{
+    report_duplicates, report_parse_errors, report_skipped, report_unused, severity_counts,
+    FileCache, ScanIssues,
+};
+use crate::stats::{peak_memory, print_stats, MemoryStats, ScanStats, Stats, StatsFormat};
 
 #[derive(clap::Parser)]
 struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     /// Path to .ts(x) file or directory
     path: Option<String>,
 
-    /// Enable verbose logging for parse errors
+    /// Show every parse error with its source location
     #[clap(short, long)]
     verbose: bool,
 
     /// Ignore warnings (only show critical/error diagnostics)
     #[clap(long)]
     ignore_warnings: bool,
+
+    /// Keep running and re-report whenever a scanned file changes
+    #[clap(short, long)]
+    watch: bool,
+
+    /// Only report duplicates with an occurrence in a file changed since the merge base with this git revision
+    #[clap(long, value_name = "REV", conflicts_with = "staged")]
+    changed_since: Option<String>,
+
+    /// Only report duplicates with an occurrence in a file staged in git
+    #[clap(long)]
+    staged: bool,
+
+    /// Only report duplicates with an occurrence in this monorepo project (Nx, package.json or pnpm workspaces)
+    #[clap(long, value_name = "NAME")]
+    only_project: Option<String>,
+
+    /// Scan exactly the files this tsconfig.json (and the projects it references) compiles, grouping findings per project
+    #[clap(short, long, value_name = "TSCONFIG")]
+    project: Option<String>,
+
+    /// Compare bodies structurally, expanding references to other declarations instead of comparing them by name
+    #[clap(long, global = true)]
+    deep: bool,
+
+    /// Also scan .js/.jsx/.mjs/.cjs files for JSDoc @typedef and @callback declarations
+    #[clap(long, global = true)]
+    js: bool,
+
+    /// Also check types in ts/typescript code fences of .md/.mdx files against the source
+    #[clap(long)]
+    docs: bool,
+
+    /// Skip .d.ts files in build output folders (tsconfig outDir/declarationDir, dist, build, out)
+    #[clap(long)]
+    skip_generated_dts: bool,
+
+    /// How to report duplicates: diagnostics on stderr, or a self-contained HTML page or a Markdown summary on stdout
+    #[clap(long, value_enum, default_value = "text")]
+    format: OutputFormat,
+
+    /// Detail at most this many findings in a Markdown summary
+    #[clap(long, value_name = "N")]
+    max_findings: Option<usize>,
+
+    /// Link Markdown locations to URL/<file>#L<line>, e.g. a blob URL of the commit
+    #[clap(long, value_name = "URL")]
+    link_base: Option<String>,
+
+    /// Print statistics about the scan and the declarations it found: totals per kind, the largest and most duplicated declarations, duplication per directory or project, and memory use
+    #[clap(long, value_enum, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "table")]
+    stats: Option<StatsFormat>,
+
+    /// Write the statistics to this file instead of the terminal
+    #[clap(long, value_name = "FILE", requires = "stats")]
+    stats_output: Option<PathBuf>,
+
+    /// Also report declarations that are never referenced, and exported ones no other file uses
+    #[clap(long)]
+    unused: bool,
 }
 
-fn parse_ts_code(
-    code: &str,
-    filename: &str,
-    results: &mut HashMap<String, Vec<FoundDeclarationNode>>,
-    impl_counts: &mut HashMap<String, usize>,
-    verbose: bool,
-) {
-    let allocator = Allocator::default();
-    let source_type = SourceType::from_path(filename).unwrap_or_default();
-    let parser_return = OxcParser::new(&allocator, code, source_type).parse();
-
-    if !parser_return.errors.is_empty() && verbose {
-        for error in &parser_return.errors {
-            eprintln!("Parse error in {}: {}", filename, error);
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    Text,
+    Html,
+    Markdown,
+}
 
-    let program = parser_return.program;
-
-    for stmt in &program.body {
-        match stmt {
-            Statement::TSTypeAliasDeclaration(type_alias) => {
-                let checker = TypeChecker { type_alias };
-                let found = checker.from_ast(code, filename, false, None);
-                results
-                    .entry(found.name.clone())
-                    .or_insert_with(Vec::new)
-                    .push(found);
-            }
-            Statement::TSInterfaceDeclaration(interface_decl) => {
-                let checker = InterfaceChecker { interface_decl };
-                let found = checker.from_ast(code, filename, false, None);
-                results
-                    .entry(found.name.clone())
-                    .or_insert_with(Vec::new)
-                    .push(found);
-            }
-            Statement::ClassDeclaration(class) => {
-                for imp in &class.implements {
-                    if let TSTypeName::IdentifierReference(id) = &imp.expression {
-                        *impl_counts.entry(id.name.to_string()).or_insert(0) += 1;
-                    }
-                }
-            }
-            Statement::ExportNamedDeclaration(export) => {
-                if let Some(decl) = &export.declaration {
-                    match decl {
-                        Declaration::TSTypeAliasDeclaration(type_alias) => {
-                            let checker = TypeChecker { type_alias };
-                            let found = checker.from_ast(code, filename, true, Some(export.span));
-                            results
-                                .entry(found.name.clone())
-                                .or_insert_with(Vec::new)
-                                .push(found);
-                        }
-                        Declaration::TSInterfaceDeclaration(interface_decl) => {
-                            let checker = InterfaceChecker { interface_decl };
-                            let found = checker.from_ast(code, filename, true, Some(export.span));
-                            results
-                                .entry(found.name.clone())
-                                .or_insert_with(Vec::new)
-                                .push(found);
-                        }
-                        Declaration::ClassDeclaration(class) => {
-                            for imp in &class.implements {
-                                if let TSTypeName::IdentifierReference(id) = &imp.expression {
-                                    *impl_counts.entry(id.name.to_string()).or_insert(0) += 1;
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
-            _ => {}
+impl Cli {
+    fn change_set(&self) -> Option<ChangeSet> {
+        match (&self.changed_since, self.staged) {
+            (Some(rev), _) => Some(ChangeSet::Since(rev.clone())),
+            (None, true) => Some(ChangeSet::Staged),
+            (None, false) => None,
         }
     }
 }
 
-fn find_ts_files(path: &Path) -> Vec<String> {
-    let mut ts_files = Vec::new();
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Run a language server over stdio that publishes duplicate diagnostics
+    Lsp,
+    /// Consolidate identical duplicates into a single canonical declaration
+    Fix(fix::FixArgs),
+    /// Give declarations that share a name but differ in body distinct names
+    Rename(fix::RenameArgs),
+}
 
-    for entry in WalkBuilder::new(path)
-        .hidden(true)
-        .git_ignore(true)
-        .git_global(true)
-        .git_exclude(true)
-        .build()
-        .filter_map(Result::ok)
-    {
-        if let Some(ext) = entry.path().extension() {
-            if ext == "ts" || ext == "tsx" {
-                ts_files.push(entry.path().to_string_lossy().to_string());
+/// Everything besides the analysis itself that shapes a report.
+struct ReportContext<'a> {
+    args: &'a Cli,
+    target_path: &'a str,
+    workspace: Option<&'a Workspace>,
+    ts_projects: &'a [TsProject],
+    skipped: &'a [(String, io::Error)],
+}
+
+/// Prints the report and returns every finding, before the report filters, for
+/// the statistics.
+fn print_report(
+    analyzer: &Analyzer,
+    source_cache: &mut FileCache,
+    context: &ReportContext,
+) -> Vec<Finding> {
+    let ReportContext {
+        args,
+        target_path,
+        workspace,
+        ts_projects,
+        skipped,
+    } = *context;
+
+    eprintln!(
+        "Found {} unique TS type/interface names.\n",
+        analyzer.declarations().len()
+    );
+
+    let mut parse_errors: Vec<&ParseError> = analyzer.index().parse_errors().collect();
+    parse_errors.sort_by_key(|error| (&error.filename, error.span_start));
+
+    let imports = analyzer.import_graph();
+    let mut findings = analyzer.findings_with_imports(&imports);
+    let filtered = args.change_set().is_some() || args.only_project.is_some();
+    let unfiltered = filtered.then(|| findings.clone());
+    let mut unused = if args.unused {
+        analyzer.unused_with_imports(&imports)
+    } else {
+        Vec::new()
+    };
+
+    if let Some(changes) = args.change_set() {
+        let changed = match changed_files(Path::new(target_path), &changes) {
+            Ok(changed) => changed,
+            Err(err) => {
+                eprintln!("Failed to list changed files: {}", err);
+                std::process::exit(1);
             }
+        };
+        retain_changed_groups(&mut findings, &changed);
+        unused.retain(|unused| {
+            std::fs::canonicalize(&unused.declaration.filename)
+                .is_ok_and(|path| changed.contains(&path))
+        });
+        eprintln!(
+            "Only reporting duplicates touching {} changed file{}.\n",
+            changed.len(),
+            if changed.len() == 1 { "" } else { "s" }
+        );
+    }
+
+    if let (Some(name), Some(workspace)) = (&args.only_project, workspace) {
+        if let Some(project) = workspace.project(name) {
+            retain_project(&mut findings, workspace, project);
+            unused.retain(|unused| {
+                workspace.project_for(&unused.declaration.filename) == Some(project)
+            });
+            eprintln!("Only reporting duplicates touching project '{}'.\n", name);
         }
     }
 
-    ts_files
-}
+    if args.deep {
+        let comparer = DeepComparer::new(analyzer.declarations(), Some(&imports));
+        apply_deep_comparison(&mut findings, &comparer);
+    }
+
+    // Only the files a diagnostic below points into are read back in. Parse
+    // errors are always listed in an HTML or Markdown report.
+    let show_parse_errors = args.verbose || args.format != OutputFormat::Text;
+    let reported = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Critical || !args.ignore_warnings)
+        .flat_map(|finding| [&finding.first.filename, &finding.second.filename])
+        .chain(unused.iter().map(|unused| &unused.declaration.filename))
+        .chain(
+            parse_errors
+                .iter()
+                .filter(|_| show_parse_errors)
+                .map(|error| &error.filename),
+        );
+    source_cache.load_only(reported.map(String::as_str));
+    let source_cache = &*source_cache;
+
+    if args.verbose {
+        report_parse_errors(&parse_errors, source_cache);
+    }
+
+    let issues = ScanIssues {
+        parse_errors: &parse_errors,
+        skipped,
+    };
+    let (warning_count, critical_count) = if args.format == OutputFormat::Html {
+        let project_of = |filename: &str| project_of(filename, context);
+        print!(
+            "{}",
+            render_html(
+                &findings,
+                source_cache,
+                issues,
+                args.ignore_warnings,
+                &project_of
+            )
+        );
+        severity_counts(&findings, args.ignore_warnings)
+    } else if args.format == OutputFormat::Markdown {
+        print!(
+            "{}",
+            render_markdown(
+                &findings,
+                source_cache,
+                issues,
+                args.ignore_warnings,
+                args.max_findings,
+                args.link_base.as_deref()
+            )
+        );
+        severity_counts(&findings, args.ignore_warnings)
+    } else if ts_projects.is_empty() {
+        report_duplicates(
+            &findings,
+            source_cache,
+            args.ignore_warnings,
+            workspace,
+            &imports,
+            analyzer.index(),
+        )
+    } else {
+        report_per_project(&findings, source_cache, context, &imports, analyzer.index())
+    };
+
+    if args.unused {
+        report_unused(&unused, source_cache);
+    }
 
-/// Multi-file source cache for ariadne
-struct FileCache {
-    files: HashMap<String, Source<String>>,
+    eprintln!("\nWarnings: {}", warning_count);
+    eprintln!("Critical: {}", critical_count);
+    if args.unused {
+        eprintln!("Unused: {}", unused.len());
+    }
+    if !parse_errors.is_empty() {
+        let mut files: Vec<&str> = parse_errors
+            .iter()
+            .map(|error| error.filename.as_str())
+            .collect();
+        files.dedup();
+        eprintln!(
+            "Parse errors: {} in {} file{}, whose duplicates may be incomplete{}",
+            parse_errors.len(),
+            files.len(),
+            if files.len() == 1 { "" } else { "s" },
+            if args.verbose {
+                ""
+            } else {
+                " (run with --verbose to see them)"
+            }
+        );
+    }
+    unfiltered.unwrap_or(findings)
 }
 
-impl FileCache {
-    fn new() -> Self {
-        Self {
-            files: HashMap::new(),
+/// Reports findings grouped under the tsconfig project that compiles their first
+/// occurrence.
+fn report_per_project(
+    findings: &[Finding],
+    source_cache: &FileCache,
+    context: &ReportContext,
+    imports: &ImportGraph,
+    index: &DeclarationIndex,
+) -> (usize, usize) {
+    let ts_projects = context.ts_projects;
+    let mut groups: Vec<Vec<Finding>> = vec![Vec::new(); ts_projects.len()];
+    for finding in findings {
+        let owner = ts_projects
+            .iter()
+            .position(|project| project.contains(Path::new(&finding.first.filename)))
+            .unwrap_or(0);
+        groups[owner].push(finding.clone());
+    }
+
+    let (mut warning_count, mut critical_count) = (0, 0);
+    for (project, group) in ts_projects.iter().zip(&groups) {
+        if group.is_empty() {
+            continue;
         }
+        eprintln!("tsconfig: {}\n", project.config.path.display());
+        let (warnings, critical) = report_duplicates(
+            group,
+            source_cache,
+            context.args.ignore_warnings,
+            context.workspace,
+            imports,
+            index,
+        );
+        warning_count += warnings;
+        critical_count += critical;
     }
+    (warning_count, critical_count)
+}
 
-    fn insert(&mut self, filename: String, source: String) {
-        self.files.insert(filename, Source::from(source));
+/// The workspace project a file belongs to, else the tsconfig project
+/// compiling it.
+fn project_of(filename: &str, context: &ReportContext) -> Option<String> {
+    if let Some(project) = context
+        .workspace
+        .and_then(|workspace| workspace.project_for(filename))
+    {
+        return Some(project.name.clone());
     }
+    context
+        .ts_projects
+        .iter()
+        .find(|project| project.contains(Path::new(filename)))
+        .map(|project| project.config.path.display().to_string())
 }
 
-#[allow(refining_impl_trait)]
-impl Cache<String> for &FileCache {
-    type Storage = String;
+/// The landscape of every declaration scanned, regardless of report filters,
+/// grouped by project, or by directory outside of any. `findings` are the
+/// unfiltered ones [`print_report`] returns.
+fn landscape(analyzer: &Analyzer, findings: &[Finding], context: &ReportContext) -> Landscape {
+    let group_of =
+        |filename: &str| project_of(filename, context).unwrap_or_else(|| directory_of(filename));
+    Landscape::new(analyzer.declarations(), findings, group_of)
+}
 
-    fn fetch(&mut self, id: &String) -> Result<&Source<String>, Box<dyn fmt::Debug + '_>> {
-        self.files
-            .get(id)
-            .ok_or_else(|| Box::new(format!("Unknown file: {}", id)) as Box<dyn fmt::Debug>)
+/// Resolves imports with the root tsconfig's `paths`/`baseUrl` when there is one.
+fn module_resolver(target_path: &str, ts_projects: &[TsProject]) -> ModuleResolver {
+    match ts_projects.first() {
+        Some(project) => ModuleResolver::from_tsconfig(&project.config),
+        None => TsConfig::load(Path::new(target_path))
+            .map(|config| ModuleResolver::from_tsconfig(&config))
+            .unwrap_or_default(),
     }
+}
 
-    fn display<'a>(&self, id: &'a String) -> Option<Box<dyn fmt::Display + 'a>> {
-        Some(Box::new(id.clone()))
-    }
+/// Where the tsconfig(s) emit declaration files.
+fn output_dirs(target_path: &str, ts_projects: &[TsProject]) -> Vec<PathBuf> {
+    let configs: Vec<TsConfig> = if ts_projects.is_empty() {
+        TsConfig::load(Path::new(target_path)).into_iter().collect()
+    } else {
+        ts_projects
+            .iter()
+            .map(|project| project.config.clone())
+            .collect()
+    };
+    configs
+        .iter()
+        .flat_map(|config| {
+            [
+                config.option_path("outDir"),
+                config.option_path("declarationDir"),
+            ]
+        })
+        .flatten()
+        .collect()
 }
 
+/// Exit code when the scan completed but some files could not be read.
+const EXIT_SKIPPED: i32 = 3;
+
 fn main() {
     let args = Cli::parse();
-    let target_path = args.path.unwrap_or_else(|| ".".to_string());
-    let paths = find_ts_files(Path::new(&target_path));
 
-    let mut results: HashMap<String, Vec<FoundDeclarationNode>> = HashMap::new();
-    let mut impl_counts: HashMap<String, usize> = HashMap::new();
+    if let Some(Command::Lsp) = args.command {
+        if let Err(err) = lsp::run(args.deep) {
+            eprintln!("[lsp] {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+    match &args.command {
+        Some(Command::Fix(fix_args)) => return fix::run(fix_args, args.js),
+        Some(Command::Rename(rename_args)) => return fix::run_rename(rename_args, args.js),
+        _ => {}
+    }
+    // HTML and Markdown reports are printed to stdout, so JSON statistics
+    // need somewhere else to go.
+    if args.stats == Some(StatsFormat::Json)
+        && args.format != OutputFormat::Text
+        && args.stats_output.is_none()
+    {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "`--stats json` and `--format html|markdown` both print to stdout; \
+                 pass `--stats-output <FILE>`",
+            )
+            .exit();
+    }
+
+    let ts_projects = match &args.project {
+        Some(tsconfig) => match resolve_projects(Path::new(tsconfig)) {
+            Ok(projects) => projects,
+            Err(err) => {
+                eprintln!("Failed to load {}: {}", tsconfig, err);
+                std::process::exit(1);
+            }
+        },
+        None => Vec::new(),
+    };
+
+    let target_path = args.path.clone().unwrap_or_else(|| {
+        ts_projects
+            .first()
+            .map(|project| project.config.dir())
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(|dir| dir.to_string_lossy().to_string())
+            .unwrap_or_else(|| ".".to_string())
+    });
+
+    let workspace = Workspace::discover(Path::new(&target_path));
+    if let Some(name) = &args.only_project {
+        let known = workspace
+            .as_ref()
+            .is_some_and(|workspace| workspace.project(name).is_some());
+        if !known {
+            let names: Vec<&str> = workspace
+                .iter()
+                .flat_map(|workspace| &workspace.projects)
+                .map(|project| project.name.as_str())
+                .collect();
+            eprintln!(
+                "Unknown project '{}'. Known projects: {}",
+                name,
+                if names.is_empty() {
+                    "none".to_string()
+                } else {
+                    names.join(", ")
+                }
+            );
+            std::process::exit(1);
+        }
+    }
+    let paths = if ts_projects.is_empty() {
+        find_source_files(Path::new(&target_path), args.js)
+    } else {
+        let mut paths: Vec<String> = ts_projects
+            .iter()
+            .flat_map(|project| project.files.iter().cloned())
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    };
+
+    let mut paths = paths;
+    if args.docs {
+        paths.extend(find_doc_files(Path::new(&target_path)));
+    }
+
+    // Watch mode tracks exactly the files this selects.
+    let output_dirs = output_dirs(&target_path, &ts_projects);
+    let is_scanned = |path: &Path| {
+        let kind = if is_doc_file(path) {
+            args.docs
+        } else if ts_projects.is_empty() {
+            is_source_file(path, args.js)
+        } else {
+            ts_projects.iter().any(|project| project.contains(path))
+        };
+        kind && !(args.skip_generated_dts && is_generated_declaration(path, &output_dirs))
+    };
+    paths.retain(|path| is_scanned(Path::new(path)));
+    let paths = paths;
+
+    let resolver = module_resolver(&target_path, &ts_projects);
+
+    let mut analyzer = Analyzer::new().resolver(resolver);
     let mut source_cache = FileCache::new();
 
     let pb = ProgressBar::new(paths.len() as u64);
@@ -181,212 +526,101 @@ fn main() {
             .progress_chars("▇▆▅▄▃▂ "),
     );
 
+    let mut skipped = Vec::new();
+    let mut bytes_read = 0;
+    let mut analysis_time = Duration::ZERO;
     for path in &paths {
-        let code = std::fs::read_to_string(path).expect("Failed to read source file");
-        source_cache.insert(path.clone(), code.clone());
-        parse_ts_code(&code, path, &mut results, &mut impl_counts, args.verbose);
-        pb.inc(1);
-    }
-    pb.finish_and_clear();
-
-    eprintln!("Found {} unique TS type/interface names.\n", results.len());
-
-    let mut warning_count: usize = 0;
-    let mut critical_count: usize = 0;
-
-    for (type_name, types) in &results {
-        if types.len() <= 1 {
-            continue;
-        }
-
-        for i in 0..types.len() {
-            for j in (i + 1)..types.len() {
-                let type_a = &types[i];
-                let type_b = &types[j];
-
-                let is_critical = type_a.body == type_b.body;
-
-                if !is_critical && args.ignore_warnings {
-                    continue;
-                }
-
-                let kind_label = match (&type_a.ast_node_variant, &type_b.ast_node_variant) {
-                    (shared_lib::AstNodeVariant::Type, shared_lib::AstNodeVariant::Type) => "type",
-                    (
-                        shared_lib::AstNodeVariant::Interface,
-                        shared_lib::AstNodeVariant::Interface,
-                    ) => "interface",
-                    _ => "declaration",
-                };
-
-                if is_critical {
-                    critical_count += 1;
-
-                    let mut report = Report::build(
-                        ReportKind::Error,
-                        (type_a.filename.clone(), type_a.span_start..type_a.span_end),
-                    )
-                    .with_message(format!(
-                        "Duplicate {} '{}' with identical body",
-                        kind_label, type_name
-                    ))
-                    .with_label(
-                        Label::new((type_a.filename.clone(), type_a.span_start..type_a.span_end))
-                            .with_message("first defined here")
-                            .with_color(Color::Red),
-                    )
-                    .with_label(
-                        Label::new((type_b.filename.clone(), type_b.span_start..type_b.span_end))
-                            .with_message("also defined here with the same body")
-                            .with_color(Color::Red),
-                    )
-                    .with_note(format!(
-                        "Consider merging into a single shared {} definition.",
-                        kind_label
-                    ));
-
-                    if kind_label == "interface" {
-                        let count = impl_counts.get(type_name.as_str()).copied().unwrap_or(0);
-                        report = report.with_help(format!(
-                            "Found {} class implementation{} of '{}'.",
-                            count,
-                            if count == 1 { "" } else { "s" },
-                            type_name
-                        ));
-                    }
-
-                    report.finish().eprint(&source_cache).unwrap();
-                } else {
-                    warning_count += 1;
-
-                    let mut report = Report::build(
-                        ReportKind::Warning,
-                        (type_a.filename.clone(), type_a.span_start..type_a.span_end),
-                    )
-                    .with_message(format!(
-                        "Duplicate {} name '{}' with different body",
-                        kind_label, type_name
-                    ))
-                    .with_label(
-                        Label::new((type_a.filename.clone(), type_a.span_start..type_a.span_end))
-                            .with_message("defined here")
-                            .with_color(Color::Yellow),
-                    )
-                    .with_label(
-                        Label::new((type_b.filename.clone(), type_b.span_start..type_b.span_end))
-                            .with_message("also defined here with a different body")
-                            .with_color(Color::Yellow),
-                    )
-                    .with_help(format!(
-                        "These {}s share a name but differ in structure. Consider renaming one.",
-                        kind_label
-                    ));
-
-                    if kind_label == "interface" {
-                        let count = impl_counts.get(type_name.as_str()).copied().unwrap_or(0);
-                        report = report.with_note(format!(
-                            "Found {} class implementation{} of '{}'.",
-                            count,
-                            if count == 1 { "" } else { "s" },
-                            type_name
-                        ));
-                    }
-
-                    report.finish().eprint(&source_cache).unwrap();
-                }
+        match read_source(Path::new(path)) {
+            Ok(code) => {
+                bytes_read += code.len();
+                let started = Instant::now();
+                analyzer.add_source(path, &code);
+                analysis_time += started.elapsed();
             }
+            Err(err) => skipped.push((path.clone(), err)),
         }
+        pb.inc(1);
     }
-
-    eprintln!("\nWarnings: {}", warning_count);
-    eprintln!("Critical: {}", critical_count);
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_impl_count_single_class() {
-        let code = r#"
-            interface IFoo { x: string; }
-            class Bar implements IFoo { x = "hi"; }
-        "#;
-        let mut results = HashMap::new();
-        let mut impl_counts = HashMap::new();
-        parse_ts_code(code, "test.ts", &mut results, &mut impl_counts, false);
-        assert_eq!(impl_counts.get("IFoo").copied().unwrap_or(0), 1);
+    pb.finish_and_clear();
+    report_skipped(&skipped);
+
+    if let Some(workspace) = &workspace {
+        eprintln!(
+            "Found {} project{} in workspace {}.",
+            workspace.projects.len(),
+            if workspace.projects.len() == 1 {
+                ""
+            } else {
+                "s"
+            },
+            workspace.root.display()
+        );
     }
 
-    #[test]
-    fn test_impl_count_multiple_classes() {
-        let code = r#"
-            interface IFoo { x: string; }
-            class A implements IFoo { x = "a"; }
-            class B implements IFoo { x = "b"; }
-            class C implements IFoo { x = "c"; }
-        "#;
-        let mut results = HashMap::new();
-        let mut impl_counts = HashMap::new();
-        parse_ts_code(code, "test.ts", &mut results, &mut impl_counts, false);
-        assert_eq!(impl_counts.get("IFoo").copied().unwrap_or(0), 3);
+    if !ts_projects.is_empty() {
+        eprintln!(
+            "Found {} tsconfig project{} compiling {} file{}.",
+            ts_projects.len(),
+            if ts_projects.len() == 1 { "" } else { "s" },
+            paths.len(),
+            if paths.len() == 1 { "" } else { "s" }
+        );
     }
 
-    #[test]
-    fn test_impl_count_no_implementations() {
-        let code = r#"
-            interface IFoo { x: string; }
-        "#;
-        let mut results = HashMap::new();
-        let mut impl_counts = HashMap::new();
-        parse_ts_code(code, "test.ts", &mut results, &mut impl_counts, false);
-        assert_eq!(impl_counts.get("IFoo").copied().unwrap_or(0), 0);
+    let context = ReportContext {
+        args: &args,
+        target_path: &target_path,
+        workspace: workspace.as_ref(),
+        ts_projects: &ts_projects,
+        skipped: &skipped,
+    };
+    let findings = print_report(&analyzer, &mut source_cache, &context);
+    if !skipped.is_empty() {
+        eprintln!("Skipped: {}", skipped.len());
     }
-
-    #[test]
-    fn test_impl_count_exported_class() {
-        let code = r#"
-            interface IFoo { x: string; }
-            export class Bar implements IFoo { x = "hi"; }
-        "#;
-        let mut results = HashMap::new();
-        let mut impl_counts = HashMap::new();
-        parse_ts_code(code, "test.ts", &mut results, &mut impl_counts, false);
-        assert_eq!(impl_counts.get("IFoo").copied().unwrap_or(0), 1);
+    if let Some(format) = args.stats {
+        let files_read = paths.len() - skipped.len();
+        let stats = Stats {
+            scan: ScanStats::new(files_read, skipped.len(), analysis_time),
+            landscape: landscape(&analyzer, &findings, &context),
+            memory: MemoryStats {
+                files_read,
+                bytes_read,
+                files_kept: source_cache.len(),
+                bytes_kept: source_cache.bytes(),
+                peak: peak_memory(),
+            },
+        };
+        print_stats(&stats, format, args.stats_output.as_deref());
     }
 
-    #[test]
-    fn test_impl_count_multiple_interfaces() {
-        let code = r#"
-            interface IFoo { x: string; }
-            interface IBar { y: number; }
-            class A implements IFoo { x = "a"; }
-            class B implements IBar { y = 1; }
-            class C implements IFoo { x = "c"; }
-        "#;
-        let mut results = HashMap::new();
-        let mut impl_counts = HashMap::new();
-        parse_ts_code(code, "test.ts", &mut results, &mut impl_counts, false);
-        assert_eq!(impl_counts.get("IFoo").copied().unwrap_or(0), 2);
-        assert_eq!(impl_counts.get("IBar").copied().unwrap_or(0), 1);
+    if args.watch {
+        eprintln!("\n[watch] Watching {} for changes...", target_path);
+        let result = watch::watch(
+            &target_path,
+            &mut analyzer,
+            &mut source_cache,
+            &mut skipped,
+            &is_scanned,
+            ts_projects.is_empty(),
+            |analyzer, source_cache, skipped| {
+                let context = ReportContext {
+                    args: &args,
+                    target_path: &target_path,
+                    workspace: workspace.as_ref(),
+                    ts_projects: &ts_projects,
+                    skipped,
+                };
+                print_report(analyzer, source_cache, &context);
+            },
+        );
+        if let Err(err) = result {
+            eprintln!("[watch] {}", err);
+            std::process::exit(1);
+        }
     }
 
-    #[test]
-    fn test_impl_count_accumulates_across_files() {
-        let mut results = HashMap::new();
-        let mut impl_counts = HashMap::new();
-
-        let code1 = r#"
-            interface IFoo { x: string; }
-            class A implements IFoo { x = "a"; }
-        "#;
-        parse_ts_code(code1, "file1.ts", &mut results, &mut impl_counts, false);
-
-        let code2 = r#"
-            class B implements IFoo { x = "b"; }
-        "#;
-        parse_ts_code(code2, "file2.ts", &mut results, &mut impl_counts, false);
-
-        assert_eq!(impl_counts.get("IFoo").copied().unwrap_or(0), 2);
+    if !skipped.is_empty() {
+        std::process::exit(EXIT_SKIPPED);
     }
 }
@@ -0,0 +1,317 @@
+use std::fmt::Write;
+
+use tta::{Finding, FoundDeclarationNode, Severity};
+
+use crate::report::{
+    body_diff, group_findings, severity_counts, DuplicateGroup, FileCache, ScanIssues,
+};
+
+/// A summary for a pull request comment or CI job summary: counts, a table of
+/// the top duplicate groups and a collapsible section per group with the
+/// source of each pair. Groups are ordered critical first, then by number of
+/// copies; once `max_findings` pairs are shown the rest are only counted.
+///
+/// Parse errors and skipped files are listed at the end.
+///
+/// Locations link to `{link_base}/{file}#L{line}` when `link_base` is given,
+/// else to the file relative to the comment.
+pub fn render_markdown(
+    findings: &[Finding],
+    source_cache: &FileCache,
+    issues: ScanIssues,
+    ignore_warnings: bool,
+    max_findings: Option<usize>,
+    link_base: Option<&str>,
+) -> String {
+    let mut groups = group_findings(findings, ignore_warnings);
+    groups.sort_by_key(|group| {
+        (
+            group.severity() == Severity::Warning,
+            std::cmp::Reverse(group.copies().len()),
+            group.name,
+        )
+    });
+
+    let (warnings, critical) = severity_counts(findings, ignore_warnings);
+    let mut out = String::from("## Duplicate types\n\n");
+    if groups.is_empty() {
+        out.push_str("No duplicate types found.\n");
+        render_issues(&mut out, issues, source_cache, link_base);
+        return out;
+    }
+    let _ = writeln!(
+        out,
+        "**{} critical**, **{} warning{}** in {} group{}.\n",
+        critical,
+        warnings,
+        if warnings == 1 { "" } else { "s" },
+        groups.len(),
+        if groups.len() == 1 { "" } else { "s" }
+    );
+
+    // Whole groups until the cap, then as many pairs of the next one as fit.
+    let mut budget = max_findings.unwrap_or(usize::MAX);
+    let mut shown: Vec<(&DuplicateGroup, usize)> = Vec::new();
+    for group in &groups {
+        if budget == 0 {
+            break;
+        }
+        let pairs = group.findings.len().min(budget);
+        budget -= pairs;
+        shown.push((group, pairs));
+    }
+
+    out.push_str("| | Name | Kind | Copies | Locations |\n|---|---|---|---|---|\n");
+    for (group, _) in &shown {
+        let locations: Vec<String> = group
+            .copies()
+            .iter()
+            .map(|node| link(node, link_base))
+            .collect();
+        let _ = writeln!(
+            out,
+            "| {} | `{}` | {} | {} | {} |",
+            severity_label(group.severity()),
+            group.name,
+            group.findings[0].kind,
+            group.copies().len(),
+            locations.join("<br>")
+        );
+    }
+    out.push('\n');
+
+    for (group, pairs) in &shown {
+        let _ = writeln!(
+            out,
+            "<details>\n<summary><code>{}</code>: {} {}, {} copies</summary>\n",
+            group.name,
+            severity_label(group.severity()),
+            group.findings[0].kind,
+            group.copies().len()
+        );
+        for finding in &group.findings[..*pairs] {
+            render_pair(&mut out, finding, source_cache, issues, link_base);
+        }
+        if *pairs < group.findings.len() {
+            let _ = writeln!(
+                out,
+                "_{} more pair{} of `{}` not shown._\n",
+                group.findings.len() - pairs,
+                if group.findings.len() - pairs == 1 {
+                    ""
+                } else {
+                    "s"
+                },
+                group.name
+            );
+        }
+        out.push_str("</details>\n\n");
+    }
+
+    let total: usize = groups.iter().map(|group| group.findings.len()).sum();
+    let detailed: usize = shown.iter().map(|(_, pairs)| pairs).sum();
+    if detailed < total {
+        let _ = writeln!(
+            out,
+            "_Showing {} of {} findings; run `tta` locally for the full report._",
+            detailed, total
+        );
+    }
+    render_issues(&mut out, issues, source_cache, link_base);
+    out
+}
+
+/// Every parse error with its line, then every skipped file.
+fn render_issues(
+    out: &mut String,
+    issues: ScanIssues,
+    source_cache: &FileCache,
+    link_base: Option<&str>,
+) {
+    if !issues.parse_errors.is_empty() {
+        let _ = writeln!(
+            out,
+            "\n### Parse errors\n\n{}\n",
+            issues.parse_error_summary()
+        );
+        for error in issues.parse_errors {
+            let _ = writeln!(
+                out,
+                "- {}: {}",
+                link_at(&error.filename, error.line, link_base),
+                error.message
+            );
+            if let Some(line) = source_cache.line(&error.filename, error.line) {
+                let line = line.trim();
+                if line.contains('`') {
+                    let _ = writeln!(out, "  `` {} ``", line);
+                } else {
+                    let _ = writeln!(out, "  `{}`", line);
+                }
+            }
+        }
+    }
+    if !issues.skipped.is_empty() {
+        out.push_str("\n### Skipped files\n\n");
+        for (filename, err) in issues.skipped {
+            let _ = writeln!(out, "- `{}`: {}", filename, err);
+        }
+    }
+}
+
+fn render_pair(
+    out: &mut String,
+    finding: &Finding,
+    source_cache: &FileCache,
+    issues: ScanIssues,
+    link_base: Option<&str>,
+) {
+    let verdict = match (finding.severity, finding.documented_copy()) {
+        (Severity::Critical, _) => "identical body",
+        (Severity::Warning, Some(_)) => "documentation out of date",
+        (Severity::Warning, None) => "different body",
+    };
+    let _ = writeln!(
+        out,
+        "{} ↔ {}: {}\n",
+        link(&finding.first, link_base),
+        link(&finding.second, link_base),
+        verdict
+    );
+    if let Some(note) = issues.incomplete_note(finding) {
+        let _ = writeln!(out, "> {}\n", note);
+    }
+    for node in [&finding.first, &finding.second] {
+        let code = source_cache.excerpt(node).unwrap_or(&node.body);
+        let language = if node.filename.ends_with(".tsx") {
+            "tsx"
+        } else {
+            "ts"
+        };
+        out.push_str(&fenced(language, code));
+    }
+    if finding.severity == Severity::Warning {
+        let diff = body_diff(finding);
+        let changes: Vec<&str> = diff
+            .lines()
+            .filter(|line| line.starts_with("+ ") || line.starts_with("- "))
+            .collect();
+        if !changes.is_empty() {
+            out.push_str(&fenced("diff", &changes.join("\n")));
+        }
+    }
+}
+
+fn severity_label(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "🔴 critical",
+        Severity::Warning => "🟡 warning",
+    }
+}
+
+fn link(node: &FoundDeclarationNode, link_base: Option<&str>) -> String {
+    link_at(&node.filename, node.line, link_base)
+}
+
+fn link_at(filename: &str, line: usize, link_base: Option<&str>) -> String {
+    let target = match link_base {
+        Some(base) => format!("{}/{}", base.trim_end_matches('/'), filename),
+        None => filename.to_string(),
+    };
+    format!(
+        "[{}:{}]({}#L{})",
+        filename,
+        line,
+        target.replace(' ', "%20"),
+        line
+    )
+}
+
+/// A code block whose fence is longer than any backtick run inside `code`.
+fn fenced(language: &str, code: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for ch in code.chars() {
+        run = if ch == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat((longest + 1).max(3));
+    format!("{fence}{language}\n{}\n{fence}\n\n", code.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use tta::parser::ParseError;
+    use tta::Analyzer;
+
+    #[test]
+    fn test_caps_detailed_findings() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a.ts", "interface A { x: string }\ntype B = string;");
+        analyzer.add_source("b.ts", "interface A { y: string }\ntype B = string;");
+        analyzer.add_source("c.ts", "interface A { z: string }");
+        let findings = analyzer.findings();
+        let summary = render_markdown(
+            &findings,
+            &FileCache::new(),
+            ScanIssues::default(),
+            false,
+            Some(2),
+            None,
+        );
+
+        assert!(summary.contains("**1 critical**, **3 warnings** in 2 groups."));
+        // The critical group comes first, then only one pair of `A` fits.
+        assert!(summary.find("<code>B</code>") < summary.find("<code>A</code>"));
+        assert_eq!(summary.matches("<details>").count(), 2);
+        assert!(summary.contains("_2 more pairs of `A` not shown._"));
+        assert!(summary.contains("_Showing 2 of 4 findings;"));
+        assert!(summary.contains("[a.ts:2](a.ts#L2)"));
+    }
+
+    #[test]
+    fn test_lists_parse_errors_and_skipped_files() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a.ts", "interface A { x: string }\nconst a;");
+        analyzer.add_source("b.ts", "interface A { x: string }");
+        let findings = analyzer.findings();
+        let parse_errors: Vec<&ParseError> = analyzer.index().parse_errors().collect();
+        let skipped = [(
+            "c.ts".to_string(),
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid UTF-8 at line 1, column 1",
+            ),
+        )];
+        let mut source_cache = FileCache::new();
+        source_cache.insert(
+            "a.ts".to_string(),
+            "interface A { x: string }\nconst a;".to_string(),
+        );
+        let issues = ScanIssues {
+            parse_errors: &parse_errors,
+            skipped: &skipped,
+        };
+        let summary = render_markdown(&findings, &source_cache, issues, false, None, None);
+
+        assert!(
+            summary.contains("> a.ts has parse errors; duplicates involving it may be incomplete.")
+        );
+        assert!(summary.contains("### Parse errors\n\n1 parse error in 1 file;"));
+        assert!(summary.contains("- [a.ts:2](a.ts#L2): "));
+        assert!(summary.contains("  `const a;`"));
+        assert!(
+            summary.contains("### Skipped files\n\n- `c.ts`: invalid UTF-8 at line 1, column 1")
+        );
+    }
+
+    #[test]
+    fn test_fence_outlasts_backticks() {
+        assert_eq!(
+            fenced("ts", "type A = `${string}```;"),
+            "````ts\ntype A = `${string}```;\n````\n\n"
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use serde::Serialize;
+
+/// One difference between the members of two object-shaped bodies. Members
+/// are shown as serialized, e.g. `readonly id?: string`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum MemberChange {
+    /// Only in the second body.
+    Added(String),
+    /// Only in the first body.
+    Removed(String),
+    /// In both, with a different type or modifiers.
+    Changed { first: String, second: String },
+}
+
+/// Member-level differences between two serialized bodies, in the order of the
+/// first body followed by members only the second has. `None` when either body
+/// isn't an object type (`{ ... }`).
+pub fn member_diff(first: &str, second: &str) -> Option<Vec<MemberChange>> {
+    let first = members(first)?;
+    let second = members(second)?;
+
+    let mut changes = Vec::new();
+    for (name, member) in &first {
+        match second.iter().find(|(other, _)| other == name) {
+            None => changes.push(MemberChange::Removed(member.to_string())),
+            Some((_, other)) if other != member => changes.push(MemberChange::Changed {
+                first: member.to_string(),
+                second: other.to_string(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (name, member) in &second {
+        if !first.iter().any(|(other, _)| other == name) {
+            changes.push(MemberChange::Added(member.to_string()));
+        }
+    }
+    Some(changes)
+}
+
+/// Renders `changes` like the hunk of a unified diff.
+pub fn format_member_diff(changes: &[MemberChange]) -> String {
+    let mut lines = Vec::new();
+    for change in changes {
+        match change {
+            MemberChange::Added(member) => lines.push(format!("+ {}", member)),
+            MemberChange::Removed(member) => lines.push(format!("- {}", member)),
+            MemberChange::Changed { first, second } => {
+                lines.push(format!("- {}", first));
+                lines.push(format!("+ {}", second));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// The top-level members of an object body as `(name, member)` pairs.
+fn members(body: &str) -> Option<Vec<(&str, &str)>> {
+    let body = body.trim();
+    if !body.starts_with('{') {
+        return None;
+    }
+    // `{ a: 1 } | { b: 2 }` starts and ends with braces but isn't one object.
+    let (close, ch, _) = nesting(body).find(|&(_, _, depth)| depth == 0)?;
+    if ch != '}' || close + 1 != body.len() {
+        return None;
+    }
+    let inner = body[1..close].trim();
+    if inner.is_empty() {
+        return Some(Vec::new());
+    }
+    Some(
+        split_top_level(inner, ';')
+            .into_iter()
+            .map(str::trim)
+            .filter(|member| !member.is_empty())
+            .map(|member| (member_name(member), member))
+            .collect(),
+    )
+}
+
+/// The key a member is matched by: its name without `readonly` or `?`, or the
+/// bracketed parameter list of an index signature.
+fn member_name(member: &str) -> &str {
+    let member = member.strip_prefix("readonly ").unwrap_or(member);
+    if member.starts_with('[') {
+        return member.find(']').map_or(member, |end| &member[..=end]);
+    }
+    let end = member.find([':', '(']).unwrap_or(member.len());
+    member[..end].trim_end_matches('?')
+}
+
+/// Splits on `separator` outside brackets and string literals.
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, ch, depth) in nesting(text) {
+        if ch == separator && depth == 0 {
+            parts.push(&text[start..i]);
+            start = i + ch.len_utf8();
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// The characters of `text` outside string literals, each with the bracket
+/// depth after it.
+fn nesting(text: &str) -> impl Iterator<Item = (usize, char, usize)> + '_ {
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut previous = None;
+
+    text.char_indices().filter_map(move |(i, ch)| {
+        let outside = match (quote, ch) {
+            (Some(open), _) if ch == open && previous != Some('\\') => {
+                quote = None;
+                None
+            }
+            (Some(_), _) => None,
+            (None, '"' | '\'' | '`') => {
+                quote = Some(ch);
+                None
+            }
+            (None, '{' | '(' | '[' | '<') => {
+                depth += 1;
+                Some(depth)
+            }
+            // `=>` is an arrow, not a closing angle bracket.
+            (None, '>') if previous == Some('=') => Some(depth),
+            (None, '}' | ')' | ']' | '>') => {
+                depth = depth.saturating_sub(1);
+                Some(depth)
+            }
+            (None, _) => Some(depth),
+        };
+        previous = Some(ch);
+        outside.map(|depth| (i, ch, depth))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_member_diff() {
+        let changes = member_diff(
+            "{ id: string; name: string; tags: Array<{ a: string; b: number }> }",
+            "{ id: number; tags: Array<{ a: string; b: number }>; email?: string }",
+        )
+        .unwrap();
+
+        assert_eq!(
+            changes,
+            vec![
+                MemberChange::Changed {
+                    first: "id: string".to_string(),
+                    second: "id: number".to_string(),
+                },
+                MemberChange::Removed("name: string".to_string()),
+                MemberChange::Added("email?: string".to_string()),
+            ]
+        );
+        assert_eq!(
+            format_member_diff(&changes),
+            "- id: string\n+ id: number\n- name: string\n+ email?: string"
+        );
+    }
+
+    #[test]
+    fn test_member_diff_matches_modifiers_and_methods() {
+        let changes = member_diff(
+            "{ readonly id: string; greet(): string; [key: string]: unknown }",
+            "{ id?: string; greet(): void; [key: string]: unknown }",
+        )
+        .unwrap();
+
+        assert_eq!(changes.len(), 2);
+        assert!(
+            matches!(&changes[0], MemberChange::Changed { second, .. } if second == "id?: string")
+        );
+        assert!(
+            matches!(&changes[1], MemberChange::Changed { second, .. } if second == "greet(): void")
+        );
+    }
+
+    #[test]
+    fn test_member_diff_needs_object_bodies() {
+        assert_eq!(member_diff("string | number", "{ a: string }"), None);
+        assert_eq!(
+            member_diff("{ kind: \"a\" } | { kind: \"b\" }", "{ kind: \"a\" }"),
+            None
+        );
+        assert_eq!(
+            member_diff("{ a: \"}\" }", "{ a: \"}\" }"),
+            Some(Vec::new())
+        );
+        assert_eq!(
+            split_top_level("a: (x: string) => void; b: string", ';'),
+            vec!["a: (x: string) => void", " b: string"]
+        );
+    }
+}
@@ -0,0 +1,461 @@
+use oxc::allocator::Allocator;
+use oxc::ast::ast::{Class, Declaration, ImportDeclarationSpecifier, Statement, TSTypeName};
+use oxc::diagnostics::OxcDiagnostic;
+use oxc::parser::Parser as OxcParser;
+use oxc::span::SourceType;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::component::parse_input;
+use crate::docs::for_each_fence_source;
+use crate::files::{is_doc_file, is_js_file};
+use crate::interface_checker::InterfaceChecker;
+use crate::jsdoc::collect_jsdoc;
+use crate::line_index::LineIndex;
+use crate::shared_lib::{CheckerFactory, FoundDeclarationNode, Implementation};
+use crate::type_checker::TypeChecker;
+use crate::usage::{collect_usages, Usage};
+
+/// Everything collected from a single source file.
+#[derive(Debug, Default)]
+pub struct ParsedFile {
+    pub declarations: Vec<FoundDeclarationNode>,
+    /// Every `implements` clause entry, attributed to its class.
+    pub implementations: Vec<Implementation>,
+    /// Every site referring to a type by name.
+    pub usages: Vec<Usage>,
+    /// `import` statements and `export ... from` re-exports, in source order.
+    pub imports: Vec<ImportRecord>,
+    /// Syntax errors; oxc recovers from most, so the rest may still be partial.
+    pub errors: Vec<ParseError>,
+}
+
+/// A syntax error in a scanned file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParseError {
+    pub filename: String,
+    pub message: String,
+    /// What oxc says at the offending span, e.g. "`;` expected".
+    pub label: Option<String>,
+    pub help: Option<String>,
+    pub span_start: usize,
+    pub span_end: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl ParseError {
+    fn new(error: &OxcDiagnostic, code: &str, lines: &LineIndex, filename: &str) -> Self {
+        let primary = error.labels.as_ref().and_then(|labels| {
+            labels
+                .iter()
+                .find(|label| label.primary())
+                .or_else(|| labels.first())
+        });
+        let span_start = primary.map_or(0, |label| label.offset()).min(code.len());
+        let span_end = primary
+            .map_or(span_start, |label| label.offset() + label.len())
+            .clamp(span_start, code.len());
+        let (line, col) = lines.line_col(span_start);
+        ParseError {
+            filename: filename.to_string(),
+            message: error.message.to_string(),
+            label: primary.and_then(|label| label.label()).map(str::to_string),
+            help: error.help.as_ref().map(|help| help.to_string()),
+            span_start,
+            span_end,
+            line,
+            col,
+        }
+    }
+}
+
+/// A single `import ... from` or `export ... from` statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportRecord {
+    /// The module specifier exactly as written, e.g. `./user` or `@app/models`.
+    pub specifier: String,
+    pub names: Vec<ImportedName>,
+    /// `export ... from` (or a local `export { x }` of an imported binding):
+    /// the names become exports of the importing file.
+    pub is_reexport: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportedName {
+    /// `{ imported as local }`; default imports use `default` as `imported`.
+    /// For re-exports `local` is the name exported from this file.
+    Named { imported: String, local: String },
+    /// `* as local`
+    Namespace(String),
+    /// `export * from`
+    All,
+}
+
+impl ParsedFile {
+    fn merge(&mut self, other: ParsedFile) {
+        self.declarations.extend(other.declarations);
+        self.implementations.extend(other.implementations);
+        self.usages.extend(other.usages);
+        self.imports.extend(other.imports);
+        self.errors.extend(other.errors);
+    }
+}
+
+/// Parses `code` and collects its top-level declarations. Declarations the
+/// built-in checkers don't handle are offered to `checkers` in order.
+pub fn parse_source(
+    code: &str,
+    filename: &str,
+    checkers: &[Box<dyn CheckerFactory>],
+) -> ParsedFile {
+    // Lines and columns are counted in the original text, whatever the
+    // blanked-out parts of a component or document are replaced with.
+    let lines = LineIndex::new(code);
+    if is_doc_file(Path::new(filename)) {
+        // Each fence is parsed alone, so a broken snippet doesn't hide the rest.
+        let mut parsed = ParsedFile::default();
+        for_each_fence_source(code, |fence, source_type| {
+            parsed.merge(parse_code(fence, source_type, &lines, filename, checkers));
+        });
+        return parsed;
+    }
+    let (code, source_type) = parse_input(code, filename);
+    parse_code(&code, source_type, &lines, filename, checkers)
+}
+
+fn parse_code(
+    code: &str,
+    source_type: SourceType,
+    lines: &LineIndex,
+    filename: &str,
+    checkers: &[Box<dyn CheckerFactory>],
+) -> ParsedFile {
+    let allocator = Allocator::default();
+    let parser_return = OxcParser::new(&allocator, code, source_type).parse();
+
+    let program = parser_return.program;
+    let mut parsed = ParsedFile {
+        errors: parser_return
+            .errors
+            .iter()
+            .map(|error| ParseError::new(error, code, lines, filename))
+            .collect(),
+        ..ParsedFile::default()
+    };
+
+    // Local `export { x }` of an imported binding re-exports it.
+    let mut local_exports: Vec<(String, String)> = Vec::new();
+
+    for stmt in &program.body {
+        let (decl, is_exported, override_span) = match stmt {
+            Statement::ImportDeclaration(import) => {
+                let names = import
+                    .specifiers
+                    .iter()
+                    .flatten()
+                    .map(|specifier| match specifier {
+                        ImportDeclarationSpecifier::ImportSpecifier(specifier) => {
+                            ImportedName::Named {
+                                imported: specifier.imported.name().to_string(),
+                                local: specifier.local.name.to_string(),
+                            }
+                        }
+                        ImportDeclarationSpecifier::ImportDefaultSpecifier(specifier) => {
+                            ImportedName::Named {
+                                imported: "default".to_string(),
+                                local: specifier.local.name.to_string(),
+                            }
+                        }
+                        ImportDeclarationSpecifier::ImportNamespaceSpecifier(specifier) => {
+                            ImportedName::Namespace(specifier.local.name.to_string())
+                        }
+                    })
+                    .collect();
+                parsed.imports.push(ImportRecord {
+                    specifier: import.source.value.to_string(),
+                    names,
+                    is_reexport: false,
+                });
+                continue;
+            }
+            Statement::ExportAllDeclaration(export) => {
+                let name = match &export.exported {
+                    Some(exported) => ImportedName::Namespace(exported.name().to_string()),
+                    None => ImportedName::All,
+                };
+                parsed.imports.push(ImportRecord {
+                    specifier: export.source.value.to_string(),
+                    names: vec![name],
+                    is_reexport: true,
+                });
+                continue;
+            }
+            Statement::ExportNamedDeclaration(export) => match &export.declaration {
+                Some(decl) => (decl, true, Some(export.span)),
+                None => {
+                    let specifiers = export.specifiers.iter().map(|specifier| {
+                        (
+                            specifier.local.name().to_string(),
+                            specifier.exported.name().to_string(),
+                        )
+                    });
+                    match &export.source {
+                        Some(source) => parsed.imports.push(ImportRecord {
+                            specifier: source.value.to_string(),
+                            names: specifiers
+                                .map(|(imported, local)| ImportedName::Named { imported, local })
+                                .collect(),
+                            is_reexport: true,
+                        }),
+                        None => local_exports.extend(specifiers),
+                    }
+                    continue;
+                }
+            },
+            _ => match stmt.as_declaration() {
+                Some(decl) => (decl, false, None),
+                None => continue,
+            },
+        };
+
+        match decl {
+            Declaration::TSTypeAliasDeclaration(type_alias) => {
+                let checker = TypeChecker { type_alias };
+                parsed.declarations.push(checker.indexed(
+                    lines,
+                    filename,
+                    is_exported,
+                    override_span,
+                ));
+            }
+            Declaration::TSInterfaceDeclaration(interface_decl) => {
+                let checker = InterfaceChecker { interface_decl };
+                parsed.declarations.push(checker.indexed(
+                    lines,
+                    filename,
+                    is_exported,
+                    override_span,
+                ));
+            }
+            Declaration::ClassDeclaration(class) => {
+                collect_implementations(class, lines, filename, &mut parsed.implementations);
+            }
+            _ => {
+                if let Some(checker) = checkers.iter().find_map(|factory| factory.checker(decl)) {
+                    parsed.declarations.push(checker.from_ast(
+                        code,
+                        filename,
+                        is_exported,
+                        override_span,
+                    ));
+                }
+            }
+        }
+    }
+
+    parsed.usages = collect_usages(&program, lines, filename);
+
+    if is_js_file(Path::new(filename)) {
+        let (declarations, usages) = collect_jsdoc(&program.comments, code, lines, filename);
+        parsed.declarations.extend(declarations);
+        parsed.usages.extend(usages);
+    }
+
+    for (local, exported) in local_exports {
+        let binding = parsed.imports.iter().find_map(|import| {
+            import.names.iter().find_map(|name| match name {
+                ImportedName::Named {
+                    imported,
+                    local: bound,
+                } if *bound == local && !import.is_reexport => {
+                    Some((import.specifier.clone(), imported.clone()))
+                }
+                _ => None,
+            })
+        });
+        if let Some((specifier, imported)) = binding {
+            parsed.imports.push(ImportRecord {
+                specifier,
+                names: vec![ImportedName::Named {
+                    imported,
+                    local: exported,
+                }],
+                is_reexport: true,
+            });
+        }
+    }
+
+    parsed
+}
+
+fn collect_implementations(
+    class: &Class,
+    lines: &LineIndex,
+    filename: &str,
+    implementations: &mut Vec<Implementation>,
+) {
+    let class_name = class
+        .id
+        .as_ref()
+        .map(|id| id.name.to_string())
+        .unwrap_or_else(|| "(anonymous class)".to_string());
+    for imp in &class.implements {
+        if let TSTypeName::IdentifierReference(id) = &imp.expression {
+            let (line, col) = lines.line_col(class.span.start as usize);
+            implementations.push(Implementation {
+                class_name: class_name.clone(),
+                interface: id.name.to_string(),
+                filename: filename.to_string(),
+                line,
+                col,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn impl_count(parsed: &ParsedFile, interface: &str) -> usize {
+        parsed
+            .implementations
+            .iter()
+            .filter(|imp| imp.interface == interface)
+            .count()
+    }
+
+    #[test]
+    fn test_collects_imports_and_reexports() {
+        let code = r#"
+            import Default, { A as B } from "./a";
+            import * as ns from "./ns";
+            export { C } from "./c";
+            export * from "./all";
+            export { B as D };
+        "#;
+        let parsed = parse_source(code, "test.ts", &[]);
+        let named = |imported: &str, local: &str| ImportedName::Named {
+            imported: imported.to_string(),
+            local: local.to_string(),
+        };
+
+        assert_eq!(
+            parsed.imports,
+            vec![
+                ImportRecord {
+                    specifier: "./a".to_string(),
+                    names: vec![named("default", "Default"), named("A", "B")],
+                    is_reexport: false,
+                },
+                ImportRecord {
+                    specifier: "./ns".to_string(),
+                    names: vec![ImportedName::Namespace("ns".to_string())],
+                    is_reexport: false,
+                },
+                ImportRecord {
+                    specifier: "./c".to_string(),
+                    names: vec![named("C", "C")],
+                    is_reexport: true,
+                },
+                ImportRecord {
+                    specifier: "./all".to_string(),
+                    names: vec![ImportedName::All],
+                    is_reexport: true,
+                },
+                ImportRecord {
+                    specifier: "./a".to_string(),
+                    names: vec![named("A", "D")],
+                    is_reexport: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collects_parse_errors() {
+        let code = "interface A { x: string }\nconst = 1;\ninterface B { y: string }\n";
+        let parsed = parse_source(code, "broken.ts", &[]);
+
+        assert!(!parsed.errors.is_empty());
+        let error = &parsed.errors[0];
+        assert_eq!(error.filename, "broken.ts");
+        assert_eq!(error.line, 2);
+        assert!(error.span_start >= code.find("const").unwrap());
+        assert!(parse_source("type A = string;", "ok.ts", &[])
+            .errors
+            .is_empty());
+    }
+
+    #[test]
+    fn test_impl_count_single_class() {
+        let code = r#"
+            interface IFoo { x: string; }
+            class Bar implements IFoo { x = "hi"; }
+        "#;
+        let parsed = parse_source(code, "test.ts", &[]);
+        assert_eq!(impl_count(&parsed, "IFoo"), 1);
+    }
+
+    #[test]
+    fn test_impl_count_multiple_classes() {
+        let code = r#"
+            interface IFoo { x: string; }
+            class A implements IFoo { x = "a"; }
+            class B implements IFoo { x = "b"; }
+            class C implements IFoo { x = "c"; }
+        "#;
+        let parsed = parse_source(code, "test.ts", &[]);
+        assert_eq!(impl_count(&parsed, "IFoo"), 3);
+    }
+
+    #[test]
+    fn test_impl_count_no_implementations() {
+        let code = r#"
+            interface IFoo { x: string; }
+        "#;
+        let parsed = parse_source(code, "test.ts", &[]);
+        assert_eq!(impl_count(&parsed, "IFoo"), 0);
+    }
+
+    #[test]
+    fn test_impl_count_exported_class() {
+        let code = r#"
+            interface IFoo { x: string; }
+            export class Bar implements IFoo { x = "hi"; }
+        "#;
+        let parsed = parse_source(code, "test.ts", &[]);
+        assert_eq!(impl_count(&parsed, "IFoo"), 1);
+    }
+
+    #[test]
+    fn test_impl_count_multiple_interfaces() {
+        let code = r#"
+            interface IFoo { x: string; }
+            interface IBar { y: number; }
+            class A implements IFoo { x = "a"; }
+            class B implements IBar { y = 1; }
+            class C implements IFoo { x = "c"; }
+        "#;
+        let parsed = parse_source(code, "test.ts", &[]);
+        assert_eq!(impl_count(&parsed, "IFoo"), 2);
+        assert_eq!(impl_count(&parsed, "IBar"), 1);
+    }
+
+    #[test]
+    fn test_impl_count_accumulates_across_files() {
+        let code1 = r#"
+            interface IFoo { x: string; }
+            class A implements IFoo { x = "a"; }
+        "#;
+        let file1 = parse_source(code1, "file1.ts", &[]);
+
+        let code2 = r#"
+            class B implements IFoo { x = "b"; }
+        "#;
+        let file2 = parse_source(code2, "file2.ts", &[]);
+
+        assert_eq!(impl_count(&file1, "IFoo") + impl_count(&file2, "IFoo"), 2);
+    }
+}
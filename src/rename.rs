@@ -0,0 +1,622 @@
+use oxc::allocator::Allocator;
+use oxc::ast::ast::{ImportDeclarationSpecifier, ModuleExportName, Statement};
+use oxc::parser::Parser as OxcParser;
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::analyzer::{resolve_usage, Finding, Severity};
+use crate::component::parse_input;
+use crate::consolidate::TextEdit;
+use crate::deep::is_identifier_char;
+use crate::imports::ImportGraph;
+use crate::shared_lib::FoundDeclarationNode;
+use crate::usage::Usage;
+use crate::workspace::Workspace;
+
+/// What a colliding declaration's new name is prefixed with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum PrefixRule {
+    /// The folder holding the file, e.g. `AdminUser` for `admin/user.ts`.
+    #[default]
+    Folder,
+    /// The workspace project holding the file, falling back to the folder.
+    Project,
+}
+
+impl FromStr for PrefixRule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "folder" => Ok(PrefixRule::Folder),
+            "project" => Ok(PrefixRule::Project),
+            other => Err(format!(
+                "unknown prefix rule '{}', expected folder or project",
+                other
+            )),
+        }
+    }
+}
+
+/// A new name for one declaration of a colliding pair.
+#[derive(Debug, Clone, Serialize)]
+pub struct RenameProposal {
+    pub declaration: FoundDeclarationNode,
+    pub new_name: String,
+}
+
+/// Proposes a distinct name for every declaration involved in a "same name,
+/// different body" warning. Names are prefixed per `rule`; when that doesn't
+/// tell the copies apart, or gives a name [`check_new_name`] refuses, the file
+/// name is used instead, followed by as many parent folders as it takes to tell
+/// the files apart, and a number is appended as a last resort. Names
+/// already declared anywhere are avoided. Declarations no name could be found
+/// for are left out and described in the returned messages.
+pub fn propose_renames(
+    findings: &[Finding],
+    results: &HashMap<String, Vec<FoundDeclarationNode>>,
+    imports: &ImportGraph,
+    rule: PrefixRule,
+    workspace: Option<&Workspace>,
+) -> (Vec<RenameProposal>, Vec<String>) {
+    let mut groups: BTreeMap<&str, Vec<&FoundDeclarationNode>> = BTreeMap::new();
+    for finding in findings {
+        // Drifted documentation is fixed by hand, not renamed.
+        if finding.severity != Severity::Warning || finding.documented_copy().is_some() {
+            continue;
+        }
+        let group = groups.entry(finding.name.as_str()).or_default();
+        for node in [&finding.first, &finding.second] {
+            let known = group.iter().any(|other| {
+                other.filename == node.filename && other.span_start == node.span_start
+            });
+            if !known {
+                group.push(node);
+            }
+        }
+    }
+
+    let mut taken: HashSet<String> = results.keys().cloned().collect();
+    let mut proposals: Vec<RenameProposal> = Vec::new();
+    let mut unnamed = Vec::new();
+    for (name, mut group) in groups {
+        group.sort_by(|a, b| (&a.filename, a.span_start).cmp(&(&b.filename, b.span_start)));
+
+        let by_rule: Vec<String> = group
+            .iter()
+            .map(|node| format!("{}{}", rule_prefix(node, rule, workspace), name))
+            .collect();
+        let distinct = by_rule.iter().collect::<HashSet<_>>().len() == by_rule.len();
+        let filenames: Vec<&str> = group.iter().map(|node| node.filename.as_str()).collect();
+        let by_file: Vec<String> = path_prefixes(&filenames)
+            .into_iter()
+            .map(|prefix| format!("{}{}", prefix, name))
+            .collect();
+
+        for (i, node) in group.into_iter().enumerate() {
+            let candidates = distinct
+                .then(|| &by_rule[i])
+                .into_iter()
+                .chain([&by_file[i]]);
+            let mut refused = Vec::new();
+            let mut chosen = None;
+            for candidate in candidates {
+                let mut new_name = candidate.clone();
+                let mut counter = 2;
+                while taken.contains(&new_name) {
+                    new_name = format!("{}{}", candidate, counter);
+                    counter += 1;
+                }
+                match check_new_name(node, &new_name, results, imports, &proposals) {
+                    Ok(()) => {
+                        chosen = Some(new_name);
+                        break;
+                    }
+                    Err(err) => refused.push(err),
+                }
+            }
+
+            match chosen {
+                Some(new_name) => {
+                    taken.insert(new_name.clone());
+                    proposals.push(RenameProposal {
+                        declaration: node.clone(),
+                        new_name,
+                    });
+                }
+                None => unnamed.push(format!(
+                    "No name found for '{}' in {}:{}: {}.",
+                    name,
+                    node.filename,
+                    node.line,
+                    refused.join(", ")
+                )),
+            }
+        }
+    }
+    (proposals, unnamed)
+}
+
+/// Checks a name typed in for `declaration`: it has to be a valid identifier,
+/// and must not clash with a declaration or another proposed name in the files
+/// the rename touches (the declaring file and every file importing it).
+pub fn check_new_name(
+    declaration: &FoundDeclarationNode,
+    new_name: &str,
+    results: &HashMap<String, Vec<FoundDeclarationNode>>,
+    imports: &ImportGraph,
+    others: &[RenameProposal],
+) -> Result<(), String> {
+    let mut chars = new_name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|first| is_identifier_char(first) && !first.is_ascii_digit())
+        && chars.all(is_identifier_char)
+        && !RESERVED_NAMES.contains(&new_name);
+    if !valid {
+        return Err(format!("'{}' is not a valid type name", new_name));
+    }
+
+    let files = imports.importers(&declaration.filename, &declaration.name);
+    let declared = results
+        .get(new_name)
+        .into_iter()
+        .flatten()
+        .map(|node| &node.filename);
+    let proposed = others
+        .iter()
+        .filter(|other| other.new_name == new_name)
+        .map(|other| &other.declaration.filename);
+    match declared
+        .chain(proposed)
+        .find(|file| files.contains_key(*file))
+    {
+        Some(file) => Err(format!("'{}' is already used in {}", new_name, file)),
+        None => Ok(()),
+    }
+}
+
+/// Keywords and predefined types a type can't be named after.
+const RESERVED_NAMES: &[&str] = &[
+    "any",
+    "bigint",
+    "boolean",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "never",
+    "new",
+    "null",
+    "number",
+    "object",
+    "return",
+    "string",
+    "super",
+    "switch",
+    "symbol",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "undefined",
+    "unknown",
+    "var",
+    "void",
+    "while",
+    "with",
+];
+
+/// The edits carrying out `proposals`: each declaration's name, every usage
+/// resolving to it, and the import and re-export specifiers naming it in
+/// the files that import it. `source` returns a file's current text.
+pub fn rename_edits<'s>(
+    proposals: &[RenameProposal],
+    results: &HashMap<String, Vec<FoundDeclarationNode>>,
+    usages: &[Usage],
+    imports: &ImportGraph,
+    source: impl Fn(&str) -> Option<&'s str>,
+) -> BTreeMap<String, Vec<TextEdit>> {
+    let mut edits: BTreeMap<String, Vec<TextEdit>> = BTreeMap::new();
+    let mut edit = |filename: &str, start: usize, old: &str, new_name: &str| {
+        edits
+            .entry(filename.to_string())
+            .or_default()
+            .push(TextEdit {
+                start,
+                end: start + old.len(),
+                text: new_name.to_string(),
+            });
+    };
+
+    for proposal in proposals {
+        let node = &proposal.declaration;
+        let old = node.name.as_str();
+        let new_name = proposal.new_name.as_str();
+
+        if let Some(at) = source(&node.filename)
+            .and_then(|code| find_name(code, node.span_start, node.span_end, old))
+        {
+            edit(&node.filename, at, old, new_name);
+        }
+
+        for usage in usages {
+            if usage.name != old {
+                continue;
+            }
+            let resolved = resolve_usage(results, Some(imports), usage)
+                .is_some_and(|d| d.filename == node.filename && d.span_start == node.span_start);
+            if !resolved {
+                continue;
+            }
+            if let Some(at) = source(&usage.filename)
+                .and_then(|code| find_name(code, usage.span_start, usage.span_end, old))
+            {
+                edit(&usage.filename, at, old, new_name);
+            }
+        }
+
+        let mut modules = imports.importers(&node.filename, old);
+        modules.remove(&node.filename);
+        if let Some(code) = source(&node.filename) {
+            for at in specifier_edits(code, &node.filename, old, true, |_, _| false) {
+                edit(&node.filename, at, old, new_name);
+            }
+        }
+        for (module, chain) in modules {
+            let Some(code) = source(&module) else {
+                continue;
+            };
+            let next = chain.get(1).map(String::as_str);
+            let imports_next = |from: &str, specifier: &str| {
+                next.is_some() && imports.target(from, specifier) == next
+            };
+            for at in specifier_edits(code, &module, old, false, imports_next) {
+                edit(&module, at, old, new_name);
+            }
+        }
+    }
+
+    for edits in edits.values_mut() {
+        edits.sort_by_key(|edit| edit.start);
+        edits.dedup();
+    }
+    edits
+}
+
+/// Offsets of `old` in the import and export specifiers of `code` that must
+/// follow the rename: specifiers importing or re-exporting it from a module
+/// `renamed_in` accepts, and local `export { old }` lists once the local
+/// binding itself is renamed (always the case in the declaring file).
+fn specifier_edits(
+    code: &str,
+    filename: &str,
+    old: &str,
+    declaring: bool,
+    renamed_in: impl Fn(&str, &str) -> bool,
+) -> Vec<usize> {
+    let allocator = Allocator::default();
+    let (code, source_type) = parse_input(code, filename);
+    let parsed = OxcParser::new(&allocator, &code, source_type).parse();
+
+    let mut offsets = Vec::new();
+    let mut local_exports = Vec::new();
+    let mut local_renamed = declaring;
+    for stmt in &parsed.program.body {
+        match stmt {
+            Statement::ImportDeclaration(import) => {
+                if !renamed_in(filename, &import.source.value) {
+                    continue;
+                }
+                for specifier in import.specifiers.iter().flatten() {
+                    let ImportDeclarationSpecifier::ImportSpecifier(specifier) = specifier else {
+                        continue;
+                    };
+                    if let Some(at) = export_name_offset(&specifier.imported, old) {
+                        offsets.push(at);
+                        // `import { old }` renames the local binding as well.
+                        local_renamed |= specifier.local.span.start as usize == at;
+                    }
+                }
+            }
+            Statement::ExportNamedDeclaration(export) => {
+                let offsets = match &export.source {
+                    Some(source) if renamed_in(filename, &source.value) => &mut offsets,
+                    Some(_) => continue,
+                    None => &mut local_exports,
+                };
+                offsets.extend(
+                    export
+                        .specifiers
+                        .iter()
+                        .filter_map(|specifier| export_name_offset(&specifier.local, old)),
+                );
+            }
+            _ => {}
+        }
+    }
+    if local_renamed {
+        offsets.extend(local_exports);
+    }
+    offsets
+}
+
+fn export_name_offset(name: &ModuleExportName, old: &str) -> Option<usize> {
+    match name {
+        ModuleExportName::IdentifierName(id) if id.name == old => Some(id.span.start as usize),
+        ModuleExportName::IdentifierReference(id) if id.name == old => Some(id.span.start as usize),
+        _ => None,
+    }
+}
+
+/// The first whole-word occurrence of `name` within `start..end` of `code`.
+fn find_name(code: &str, start: usize, end: usize, name: &str) -> Option<usize> {
+    let end = end.min(code.len());
+    code.get(start..end)?
+        .match_indices(name)
+        .map(|(offset, _)| start + offset)
+        .find(|&at| {
+            let before = code[..at].chars().next_back();
+            let after = code[at + name.len()..].chars().next();
+            !before.is_some_and(is_identifier_char) && !after.is_some_and(is_identifier_char)
+        })
+}
+
+fn rule_prefix(
+    node: &FoundDeclarationNode,
+    rule: PrefixRule,
+    workspace: Option<&Workspace>,
+) -> String {
+    let project = match (rule, workspace) {
+        (PrefixRule::Project, Some(workspace)) => workspace.project_for(&node.filename),
+        _ => None,
+    };
+    match project {
+        Some(project) => pascal_case(project.name.rsplit('/').next().unwrap_or(&project.name)),
+        None => folder_prefix(&node.filename),
+    }
+}
+
+fn folder_prefix(filename: &str) -> String {
+    let folder = Path::new(filename)
+        .parent()
+        .and_then(Path::file_name)
+        .map(|name| name.to_string_lossy().to_string());
+    match folder {
+        Some(folder) => pascal_case(&folder),
+        None => file_prefix(filename),
+    }
+}
+
+fn file_prefix(filename: &str) -> String {
+    let stem = Path::new(filename)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    // `user.model.ts` → `UserModel`
+    let stem = stem.strip_suffix(".d.ts").unwrap_or(&stem);
+    let stem = stem.rsplit_once('.').map_or(stem, |(stem, _)| stem);
+    pascal_case(stem)
+}
+
+/// [`file_prefix`] of each of `filenames`, followed by their parent folders
+/// (innermost first) until the prefixes are distinct or the paths run out:
+/// `2024/user.ts` and `2025/user.ts` → `User2024` and `User2025`.
+fn path_prefixes(filenames: &[&str]) -> Vec<String> {
+    let mut prefixes: Vec<String> = filenames.iter().map(|f| file_prefix(f)).collect();
+    let mut folders: Vec<_> = filenames
+        .iter()
+        .map(|filename| {
+            Path::new(filename)
+                .parent()
+                .into_iter()
+                .flat_map(Path::ancestors)
+                .filter_map(Path::file_name)
+        })
+        .collect();
+    while prefixes.iter().collect::<HashSet<_>>().len() < prefixes.len() {
+        let mut extended = false;
+        for (prefix, folders) in prefixes.iter_mut().zip(&mut folders) {
+            if let Some(folder) = folders.next() {
+                prefix.push_str(&pascal_case(&folder.to_string_lossy()));
+                extended = true;
+            }
+        }
+        if !extended {
+            break;
+        }
+    }
+    prefixes
+}
+
+/// `billing-api` → `BillingApi`
+fn pascal_case(text: &str) -> String {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consolidate::apply_edits;
+    use crate::Analyzer;
+
+    #[test]
+    fn test_pascal_case_and_prefixes() {
+        assert_eq!(pascal_case("billing-api"), "BillingApi");
+        assert_eq!(folder_prefix("src/admin/user.ts"), "Admin");
+        assert_eq!(file_prefix("src/user.model.ts"), "UserModel");
+        assert_eq!(file_prefix("src/types.d.ts"), "Types");
+        assert_eq!(
+            path_prefixes(&["a/src/user.ts", "b/src/user.ts", "src/profile.ts"]),
+            vec!["UserSrcA", "UserSrcB", "ProfileSrc"]
+        );
+    }
+
+    #[test]
+    fn test_check_new_name() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("admin/user.ts", "export interface User { id: string }");
+        analyzer.add_source(
+            "app.ts",
+            "import { User } from './admin/user';\ninterface Admin { id: string }",
+        );
+        analyzer.add_source("other.ts", "interface Member { id: string }");
+        let graph = analyzer.import_graph();
+        let results = analyzer.declarations();
+        let user = &results["User"][0];
+        let proposal = |filename: &str, new_name: &str| RenameProposal {
+            declaration: FoundDeclarationNode {
+                filename: filename.to_string(),
+                ..user.clone()
+            },
+            new_name: new_name.to_string(),
+        };
+        let check = |name: &str, others: &[RenameProposal]| {
+            check_new_name(user, name, results, &graph, others)
+        };
+
+        assert!(check("AdminUser", &[]).is_ok());
+        assert!(check("Member", &[]).is_ok());
+        assert!(check("2User", &[]).is_err());
+        assert!(check("Admin User", &[]).is_err());
+        assert!(check("string", &[]).is_err());
+        assert_eq!(
+            check("Admin", &[]),
+            Err("'Admin' is already used in app.ts".to_string())
+        );
+        assert!(check("AdminUser", &[proposal("admin/user.ts", "AdminUser")]).is_err());
+        assert!(check("AdminUser", &[proposal("other.ts", "AdminUser")]).is_ok());
+    }
+
+    #[test]
+    fn test_renames_declarations_usages_and_imports() {
+        let files = [
+            ("admin/user.ts", "export interface User { id: string }\n"),
+            (
+                "billing/user.ts",
+                "export interface User { id: number; next?: User }\nexport { User as Account };\n",
+            ),
+            (
+                "billing/index.ts",
+                "export { User } from './user';\nexport { User as Invoice } from './user';\n",
+            ),
+            (
+                "invoice.ts",
+                "import { Invoice } from './billing';\nconst c: Invoice = { id: 2 };\n",
+            ),
+            (
+                "app.ts",
+                "import { User } from './admin/user';\n\
+                 import * as billing from './billing';\n\
+                 const a: User = { id: '' };\n\
+                 const b: billing.User = { id: 1 };\n",
+            ),
+        ];
+        let mut analyzer = Analyzer::new();
+        for (filename, code) in files {
+            analyzer.add_source(filename, code);
+        }
+
+        let (proposals, unnamed) = propose_renames(
+            &analyzer.findings(),
+            analyzer.declarations(),
+            &analyzer.import_graph(),
+            PrefixRule::Folder,
+            None,
+        );
+        assert!(unnamed.is_empty());
+        let names: Vec<&str> = proposals.iter().map(|p| p.new_name.as_str()).collect();
+        assert_eq!(names, vec!["AdminUser", "BillingUser"]);
+
+        let usages: Vec<Usage> = analyzer.index().usages().cloned().collect();
+        let sources: HashMap<&str, &str> = files.into_iter().collect();
+        let edits = rename_edits(
+            &proposals,
+            analyzer.declarations(),
+            &usages,
+            &analyzer.import_graph(),
+            |filename| sources.get(filename).copied(),
+        );
+        let fixed = |filename: &str| apply_edits(sources[filename], &edits[filename]).unwrap();
+
+        assert_eq!(
+            fixed("admin/user.ts"),
+            "export interface AdminUser { id: string }\n"
+        );
+        assert_eq!(
+            fixed("billing/user.ts"),
+            "export interface BillingUser { id: number; next?: BillingUser }\n\
+             export { BillingUser as Account };\n"
+        );
+        assert_eq!(
+            fixed("billing/index.ts"),
+            "export { BillingUser } from './user';\n\
+             export { BillingUser as Invoice } from './user';\n"
+        );
+        assert!(!edits.contains_key("invoice.ts"));
+        assert_eq!(
+            fixed("app.ts"),
+            "import { AdminUser } from './admin/user';\n\
+             import * as billing from './billing';\n\
+             const a: AdminUser = { id: '' };\n\
+             const b: billing.BillingUser = { id: 1 };\n"
+        );
+    }
+
+    #[test]
+    fn test_generated_names_are_checked() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("2024/user.ts", "interface User { id: string }");
+        analyzer.add_source("2025/user.ts", "interface User { id: number }");
+        analyzer.add_source("2026/3.ts", "interface User { id: boolean }");
+
+        let (proposals, unnamed) = propose_renames(
+            &analyzer.findings(),
+            analyzer.declarations(),
+            &analyzer.import_graph(),
+            PrefixRule::Folder,
+            None,
+        );
+        let names: Vec<&str> = proposals.iter().map(|p| p.new_name.as_str()).collect();
+        assert_eq!(names, vec!["User2024User", "User2025User"]);
+        assert_eq!(
+            unnamed,
+            vec![
+                "No name found for 'User' in 2026/3.ts:1: '2026User' is not a valid type name, \
+                 '32026User' is not a valid type name."
+                    .to_string()
+            ]
+        );
+    }
+}
@@ -0,0 +1,679 @@
+use ariadne::{Cache, Color, Config, IndexType, Label, Report, ReportKind, Source};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use tta::files::read_source;
+use tta::imports::ImportGraph;
+use tta::index::DeclarationIndex;
+use tta::member_diff::{format_member_diff, member_diff};
+use tta::parser::ParseError;
+use tta::shared_lib::Implementation;
+use tta::unused::{UnusedDeclaration, UnusedKind};
+use tta::workspace::{ProjectScope, Workspace};
+use tta::{Finding, FoundDeclarationNode, Severity};
+
+/// Multi-file source cache for ariadne. Only the files diagnostics point into
+/// are kept, see [`FileCache::load_only`].
+#[derive(Default)]
+pub struct FileCache {
+    files: HashMap<String, Source<String>>,
+}
+
+impl FileCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, filename: String, source: String) {
+        self.files.insert(filename, Source::from(source));
+    }
+
+    pub fn remove(&mut self, filename: &str) {
+        self.files.remove(filename);
+    }
+
+    /// Reads whichever of `filenames` aren't cached yet and drops every other
+    /// file, so sources are only resident while a report needs them.
+    pub fn load_only<'a>(&mut self, filenames: impl IntoIterator<Item = &'a str>) {
+        let needed: HashSet<&str> = filenames.into_iter().collect();
+        self.files
+            .retain(|filename, _| needed.contains(filename.as_str()));
+        for filename in needed {
+            if self.files.contains_key(filename) {
+                continue;
+            }
+            match read_source(Path::new(filename)) {
+                Ok(code) => self.insert(filename.to_string(), code),
+                Err(err) => eprintln!("Failed to re-read {}: {}", filename, err),
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn text(&self, filename: &str) -> Option<&str> {
+        self.files.get(filename).map(|source| source.text())
+    }
+
+    /// Line `line` (1-based) of `filename`, if the file is cached.
+    pub fn line(&self, filename: &str, line: usize) -> Option<&str> {
+        self.text(filename)?.lines().nth(line.checked_sub(1)?)
+    }
+
+    /// The whole lines `node` spans, if its file is cached and still has the
+    /// declaration where it was analysed. A file edited since (say, between a
+    /// `--watch` change and its re-analysis) gives `None`, so callers show the
+    /// serialized body instead.
+    pub fn excerpt(&self, node: &FoundDeclarationNode) -> Option<&str> {
+        let source = self.text(&node.filename)?;
+        let declaration = source.get(node.span_start..node.span_end)?;
+        if !declaration.contains(node.name.as_str()) {
+            return None;
+        }
+        let start = source[..node.span_start].rfind('\n').map_or(0, |i| i + 1);
+        let end = source[node.span_end..]
+            .find('\n')
+            .map_or(source.len(), |i| node.span_end + i);
+        source.get(start..end)
+    }
+
+    /// Total size of the cached sources.
+    pub fn bytes(&self) -> usize {
+        self.files.values().map(|source| source.text().len()).sum()
+    }
+}
+
+#[allow(refining_impl_trait)]
+impl Cache<String> for &FileCache {
+    type Storage = String;
+
+    fn fetch(&mut self, id: &String) -> Result<&Source<String>, Box<dyn fmt::Debug + '_>> {
+        self.files
+            .get(id)
+            .ok_or_else(|| Box::new(format!("Unknown file: {}", id)) as Box<dyn fmt::Debug>)
+    }
+
+    fn display<'a>(&self, id: &'a String) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(id.clone()))
+    }
+}
+
+/// Prints a diagnostic for every finding and returns the `(warning, critical)` counts.
+pub fn report_duplicates(
+    findings: &[Finding],
+    source_cache: &FileCache,
+    ignore_warnings: bool,
+    workspace: Option<&Workspace>,
+    imports: &ImportGraph,
+    index: &DeclarationIndex,
+) -> (usize, usize) {
+    let mut warning_count: usize = 0;
+    let mut critical_count: usize = 0;
+
+    for finding in findings {
+        let type_name = &finding.name;
+        let kind_label = &finding.kind;
+        let type_a = &finding.first;
+        let type_b = &finding.second;
+
+        match finding.severity {
+            Severity::Critical => {
+                critical_count += 1;
+
+                let mut report = Report::build(
+                    ReportKind::Error,
+                    (type_a.filename.clone(), type_a.span_start..type_a.span_end),
+                )
+                .with_config(byte_spans())
+                .with_message(finding.message())
+                .with_label(
+                    Label::new((type_a.filename.clone(), type_a.span_start..type_a.span_end))
+                        .with_message(format!(
+                            "first defined here ({})",
+                            usage_count(finding.usages.first.len())
+                        ))
+                        .with_color(Color::Red),
+                )
+                .with_label(
+                    Label::new((type_b.filename.clone(), type_b.span_start..type_b.span_end))
+                        .with_message(format!(
+                            "also defined here with the same body ({})",
+                            usage_count(finding.usages.second.len())
+                        ))
+                        .with_color(Color::Red),
+                )
+                .with_note(match finding.ambient_copy() {
+                    Some((ambient, _)) => format!(
+                        "Import '{}' from {} instead of redeclaring it.",
+                        type_name, ambient.filename
+                    ),
+                    None => format!(
+                        "Consider merging into a single shared {} definition.",
+                        kind_label
+                    ),
+                });
+
+                if let Some(summary) = implementation_summary(finding) {
+                    report = report.with_help(summary);
+                }
+
+                if let Some(workspace) = workspace {
+                    let (note, help) = project_context(finding, workspace);
+                    report.with_notes(note);
+                    report.with_helps(help);
+                }
+                report.with_notes(import_context(finding, imports));
+                report.with_notes(parse_error_context(finding, index));
+                report.with_notes(equivalence_context(finding));
+                report.with_notes(generated_context(finding));
+                if finding.ambient_copy().is_none() {
+                    report.with_helps(keep_suggestion(finding));
+                }
+
+                eprint(report.finish(), source_cache);
+            }
+            Severity::Warning => {
+                if ignore_warnings {
+                    continue;
+                }
+                warning_count += 1;
+
+                let mut report = Report::build(
+                    ReportKind::Warning,
+                    (type_a.filename.clone(), type_a.span_start..type_a.span_end),
+                )
+                .with_config(byte_spans())
+                .with_message(finding.message())
+                .with_label(
+                    Label::new((type_a.filename.clone(), type_a.span_start..type_a.span_end))
+                        .with_message(format!(
+                            "defined here ({})",
+                            usage_count(finding.usages.first.len())
+                        ))
+                        .with_color(Color::Yellow),
+                )
+                .with_label(
+                    Label::new((type_b.filename.clone(), type_b.span_start..type_b.span_end))
+                        .with_message(format!(
+                            "also defined here with a different body ({})",
+                            usage_count(finding.usages.second.len())
+                        ))
+                        .with_color(Color::Yellow),
+                )
+                .with_help(
+                    match (finding.documented_copy(), finding.ambient_copy()) {
+                        (Some((_, source)), _) => format!(
+                            "Update the documentation to match the definition in {}:{}.",
+                            source.filename, source.line
+                        ),
+                        (_, Some((ambient, _))) => format!(
+                            "Align it with the declaration in {} or rename it.",
+                            ambient.filename
+                        ),
+                        _ => format!(
+                        "These {}s share a name but differ in structure. Consider renaming one.",
+                        kind_label
+                    ),
+                    },
+                );
+
+                report = report.with_note(body_diff(finding));
+                if let Some(summary) = implementation_summary(finding) {
+                    report = report.with_note(summary);
+                }
+
+                if let Some(workspace) = workspace {
+                    let (note, _) = project_context(finding, workspace);
+                    report.with_notes(note);
+                }
+                report.with_notes(import_context(finding, imports));
+                report.with_notes(parse_error_context(finding, index));
+                report.with_notes(generated_context(finding));
+
+                eprint(report.finish(), source_cache);
+            }
+        }
+    }
+
+    (warning_count, critical_count)
+}
+
+/// Findings sharing a name.
+pub struct DuplicateGroup<'a> {
+    pub name: &'a str,
+    pub findings: Vec<&'a Finding>,
+}
+
+impl DuplicateGroup<'_> {
+    /// Critical when any of its pairs is.
+    pub fn severity(&self) -> Severity {
+        if self
+            .findings
+            .iter()
+            .any(|finding| finding.severity == Severity::Critical)
+        {
+            Severity::Critical
+        } else {
+            Severity::Warning
+        }
+    }
+
+    /// Every declaration involved, once each, in order of appearance.
+    pub fn copies(&self) -> Vec<&FoundDeclarationNode> {
+        let mut copies: Vec<&FoundDeclarationNode> = Vec::new();
+        for finding in &self.findings {
+            for node in [&finding.first, &finding.second] {
+                if !copies.iter().any(|copy| {
+                    copy.filename == node.filename && copy.span_start == node.span_start
+                }) {
+                    copies.push(node);
+                }
+            }
+        }
+        copies
+    }
+}
+
+/// Consecutive findings with the same name, as [`find_duplicates`](tta::find_duplicates)
+/// orders them, leaving out warnings when they are ignored.
+pub fn group_findings(findings: &[Finding], ignore_warnings: bool) -> Vec<DuplicateGroup<'_>> {
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    for finding in findings {
+        if ignore_warnings && finding.severity == Severity::Warning {
+            continue;
+        }
+        match groups.last_mut() {
+            Some(group) if group.name == finding.name => group.findings.push(finding),
+            _ => groups.push(DuplicateGroup {
+                name: &finding.name,
+                findings: vec![finding],
+            }),
+        }
+    }
+    groups
+}
+
+/// The `(warning, critical)` counts [`report_duplicates`] would return, without
+/// printing anything.
+pub fn severity_counts(findings: &[Finding], ignore_warnings: bool) -> (usize, usize) {
+    let critical = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Critical)
+        .count();
+    let warnings = if ignore_warnings {
+        0
+    } else {
+        findings.len() - critical
+    };
+    (warnings, critical)
+}
+
+/// Prints an advice diagnostic for every unused declaration.
+pub fn report_unused(unused: &[UnusedDeclaration], source_cache: &FileCache) {
+    for unused in unused {
+        let node = &unused.declaration;
+        let kind_label = node.ast_node_variant.label();
+        let (message, label, help) = match unused.kind {
+            UnusedKind::Unreferenced => (
+                format!("Unused {} '{}'", kind_label, node.name),
+                "never referenced",
+                format!(
+                    "Nothing refers to this {}; consider deleting it.",
+                    kind_label
+                ),
+            ),
+            UnusedKind::NotImported => (
+                format!("Exported {} '{}' is never imported", kind_label, node.name),
+                "only used in this file",
+                "Consider dropping the export.".to_string(),
+            ),
+        };
+        let report = Report::build(
+            ReportKind::Advice,
+            (node.filename.clone(), node.span_start..node.span_end),
+        )
+        .with_config(byte_spans())
+        .with_message(message)
+        .with_label(
+            Label::new((node.filename.clone(), node.span_start..node.span_end))
+                .with_message(label)
+                .with_color(Color::Cyan),
+        )
+        .with_help(help)
+        .finish();
+        eprint(report, source_cache);
+    }
+}
+
+/// Prints every syntax error with its source label.
+pub fn report_parse_errors(errors: &[&ParseError], source_cache: &FileCache) {
+    for error in errors {
+        let span = (error.filename.clone(), error.span_start..error.span_end);
+        let mut report = Report::build(ReportKind::Error, span.clone())
+            .with_config(byte_spans())
+            .with_message(format!("Parse error: {}", error.message))
+            .with_label(
+                Label::new(span)
+                    .with_message(error.label.as_deref().unwrap_or(&error.message))
+                    .with_color(Color::Red),
+            );
+        report.with_helps(error.help.clone());
+        eprint(report.finish(), source_cache);
+    }
+}
+
+/// Lists the files that could not be read; the scan carries on without them.
+pub fn report_skipped(skipped: &[(String, io::Error)]) {
+    for (filename, err) in skipped {
+        eprintln!("Skipped {}: {}", filename, err);
+    }
+    if !skipped.is_empty() {
+        eprintln!();
+    }
+}
+
+/// Prints a diagnostic; a failure to print one doesn't stop the report.
+fn eprint(report: Report<'_, (String, Range<usize>)>, source_cache: &FileCache) {
+    if let Err(err) = report.eprint(source_cache) {
+        eprintln!("Failed to print diagnostic: {}", err);
+    }
+}
+
+/// How the two bodies differ: member by member for object types, else both
+/// bodies in full.
+pub fn body_diff(finding: &Finding) -> String {
+    let (first, second) = (&finding.first, &finding.second);
+    match member_diff(&first.body, &second.body) {
+        Some(changes) if !changes.is_empty() => format!(
+            "Members differ ({}:{} → {}:{}):\n{}",
+            first.filename,
+            first.line,
+            second.filename,
+            second.line,
+            format_member_diff(&changes)
+        ),
+        _ => format!("Bodies differ:\n- {}\n+ {}", first.body, second.body),
+    }
+}
+
+/// Spans are byte offsets into the source, not char indices.
+fn byte_spans() -> Config {
+    Config::default().with_index_type(IndexType::Byte)
+}
+
+/// For duplicates between two declaration files, which are usually emitted.
+fn generated_context(finding: &Finding) -> Option<String> {
+    (finding.first.is_ambient() && finding.second.is_ambient()).then(|| {
+        "Both copies are in declaration files; if they are generated, fix the sources they are emitted from.".to_string()
+    })
+}
+
+/// Which of the two files oxc had to recover from syntax errors in.
+fn parse_error_context(finding: &Finding, index: &DeclarationIndex) -> Option<String> {
+    incomplete_note(finding, |filename| index.has_parse_errors(filename))
+}
+
+/// Files the scan couldn't read or had to recover from syntax errors in, for
+/// the reports that don't go through ariadne.
+#[derive(Default, Clone, Copy)]
+pub struct ScanIssues<'a> {
+    /// Sorted by file.
+    pub parse_errors: &'a [&'a ParseError],
+    pub skipped: &'a [(String, io::Error)],
+}
+
+impl ScanIssues<'_> {
+    /// E.g. "3 parse errors in 2 files; duplicates involving them may be
+    /// incomplete."
+    pub fn parse_error_summary(&self) -> String {
+        let mut files: Vec<&str> = self
+            .parse_errors
+            .iter()
+            .map(|error| error.filename.as_str())
+            .collect();
+        files.dedup();
+        format!(
+            "{} parse error{} in {} file{}; duplicates involving {} may be incomplete.",
+            self.parse_errors.len(),
+            if self.parse_errors.len() == 1 {
+                ""
+            } else {
+                "s"
+            },
+            files.len(),
+            if files.len() == 1 { "" } else { "s" },
+            if files.len() == 1 { "it" } else { "them" }
+        )
+    }
+
+    /// The note the text report gives a finding in a file with parse errors.
+    pub fn incomplete_note(&self, finding: &Finding) -> Option<String> {
+        incomplete_note(finding, |filename| {
+            self.parse_errors
+                .iter()
+                .any(|error| error.filename == filename)
+        })
+    }
+}
+
+fn incomplete_note(finding: &Finding, has_errors: impl Fn(&str) -> bool) -> Option<String> {
+    let mut files = vec![&finding.first.filename];
+    if finding.second.filename != finding.first.filename {
+        files.push(&finding.second.filename);
+    }
+    files.retain(|filename| has_errors(filename));
+    (!files.is_empty()).then(|| {
+        let files: Vec<&str> = files.iter().map(|filename| filename.as_str()).collect();
+        format!(
+            "{} ha{} parse errors; duplicates involving {} may be incomplete.",
+            files.join(" and "),
+            if files.len() == 1 { "s" } else { "ve" },
+            if files.len() == 1 { "it" } else { "them" }
+        )
+    })
+}
+
+/// The import chains through which both declarations reach the same module,
+/// if any module sees both. Copies in the same file reach every importer the
+/// same way, so there is nothing to tell.
+fn import_context(finding: &Finding, imports: &ImportGraph) -> Option<String> {
+    if finding.first.filename == finding.second.filename {
+        return None;
+    }
+    let shared = imports.shared_importer(finding)?;
+    Some(format!(
+        "Both declarations are imported into '{}': {} and {}.",
+        shared.module,
+        shared.first_chain.join(" → "),
+        shared.second_chain.join(" → ")
+    ))
+}
+
+fn usage_count(count: usize) -> String {
+    format!("{} usage{}", count, if count == 1 { "" } else { "s" })
+}
+
+/// Which copy to keep when merging: the one with more usage sites, so the
+/// fewest have to be migrated. A copy nothing uses can simply be deleted.
+fn keep_suggestion(finding: &Finding) -> Option<String> {
+    let first = finding.usages.first.len();
+    let second = finding.usages.second.len();
+    if first + second == 0 {
+        return None;
+    }
+    let (keep, drop, migrate) = if first >= second {
+        (&finding.first, &finding.second, second)
+    } else {
+        (&finding.second, &finding.first, first)
+    };
+    if migrate == 0 {
+        return Some(format!(
+            "The copy in {}:{} is never used; delete it instead of merging.",
+            drop.filename, drop.line
+        ));
+    }
+    Some(format!(
+        "Keep the copy in {}:{}; {} site{} would need migrating.",
+        keep.filename,
+        keep.line,
+        migrate,
+        if migrate == 1 { "" } else { "s" }
+    ))
+}
+
+/// Which classes implement each of the two interfaces, by name and location.
+fn implementation_summary(finding: &Finding) -> Option<String> {
+    let implementations = finding.implementations.as_ref()?;
+    let describe = |node: &FoundDeclarationNode, classes: &[Implementation]| {
+        let implemented_by = if classes.is_empty() {
+            "no class implementations".to_string()
+        } else {
+            let classes: Vec<String> = classes
+                .iter()
+                .map(|class| {
+                    format!(
+                        "{} ({}:{}:{})",
+                        class.class_name, class.filename, class.line, class.col
+                    )
+                })
+                .collect();
+            format!("implemented by {}", classes.join(", "))
+        };
+        format!(
+            "'{}' in {}:{}: {}.",
+            finding.name, node.filename, node.line, implemented_by
+        )
+    };
+    Some(format!(
+        "{} {}",
+        describe(&finding.first, &implementations.first),
+        describe(&finding.second, &implementations.second)
+    ))
+}
+
+/// The nested types a deep comparison matched up, if any.
+fn equivalence_context(finding: &Finding) -> Option<String> {
+    if finding.equivalences.is_empty() {
+        return None;
+    }
+    let pairs: Vec<String> = finding
+        .equivalences
+        .iter()
+        .map(|eq| {
+            format!(
+                "{} ({}) ≡ {} ({})",
+                eq.first, eq.first_filename, eq.second, eq.second_filename
+            )
+        })
+        .collect();
+    Some(format!(
+        "Identical once nested types are expanded: {}.",
+        pairs.join(", ")
+    ))
+}
+
+/// Which project(s) a finding lives in, and for cross-project duplicates the
+/// library that should host the merged declaration.
+fn project_context(finding: &Finding, workspace: &Workspace) -> (Option<String>, Option<String>) {
+    match workspace.scope(finding) {
+        ProjectScope::Intra(project) => (
+            Some(format!(
+                "Both declarations are in project '{}'.",
+                project.name
+            )),
+            None,
+        ),
+        ProjectScope::Cross(a, b) => {
+            let note = format!(
+                "Cross-project duplicate between '{}' and '{}'.",
+                a.name, b.name
+            );
+            let help = workspace
+                .suggest_host([
+                    finding.first.filename.as_str(),
+                    finding.second.filename.as_str(),
+                ])
+                .map(|host| {
+                    let location = host
+                        .root
+                        .strip_prefix(&workspace.root)
+                        .unwrap_or(&host.root);
+                    format!(
+                        "Consider hosting '{}' in the shared library '{}' ({}).",
+                        finding.name,
+                        host.name,
+                        location.display()
+                    )
+                });
+            (Some(note), help)
+        }
+        ProjectScope::Unassigned => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tta::Analyzer;
+
+    #[test]
+    fn test_excerpt_falls_back_when_the_file_changed() {
+        let code = "// users\ninterface User { id: string }\n";
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a.ts", code);
+        let node = &analyzer.declarations()["User"][0];
+
+        let mut cache = FileCache::new();
+        cache.insert("a.ts".to_string(), code.to_string());
+        assert_eq!(cache.excerpt(node), Some("interface User { id: string }"));
+
+        cache.insert("a.ts".to_string(), "type Other = 1;".to_string());
+        assert_eq!(cache.excerpt(node), None);
+        cache.insert("a.ts".to_string(), "é".repeat(20));
+        assert_eq!(cache.excerpt(node), None);
+    }
+
+    #[test]
+    fn test_load_only_keeps_what_findings_need() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+        let (cached, evicted, uncached, unreadable) =
+            (path("a.ts"), path("b.ts"), path("c.ts"), path("gone.ts"));
+        std::fs::write(&uncached, "type C = 1;").unwrap();
+
+        let mut cache = FileCache::new();
+        cache.insert(cached.clone(), "type A = 1;".to_string());
+        cache.insert(evicted.clone(), "type B = 1;".to_string());
+
+        cache.load_only([cached.as_str(), uncached.as_str(), unreadable.as_str()]);
+
+        assert_eq!(cache.len(), 2);
+        // Cached sources are cached as they are, not re-read.
+        assert_eq!(cache.text(&cached), Some("type A = 1;"));
+        assert_eq!(cache.text(&evicted), None);
+        assert_eq!(cache.text(&uncached), Some("type C = 1;"));
+        assert_eq!(cache.text(&unreadable), None);
+    }
+
+    #[test]
+    fn test_import_context_needs_two_files() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source(
+            "a.ts",
+            "type User = { id: string };\ntype User = { id: number };",
+        );
+        analyzer.add_source("b.ts", "import type { User } from './a';");
+        let findings = analyzer.findings();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(import_context(&findings[0], &analyzer.import_graph()), None);
+    }
+}
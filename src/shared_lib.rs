@@ -1,12 +1,33 @@
+use oxc::ast::ast::Declaration;
 use oxc::span::Span;
+use serde::Serialize;
+use std::path::Path;
 
-#[derive(Debug, Clone)]
+use crate::files::{is_declaration_file, is_doc_file};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum AstNodeVariant {
     Type,
     Interface,
+    /// A JSDoc `@typedef` or `@callback` in a JavaScript file.
+    Typedef,
+    /// Declarations collected by a caller-provided `CheckerFactory`, labelled by it.
+    Custom(String),
+}
+
+impl AstNodeVariant {
+    /// Human readable name used in diagnostics, e.g. "interface".
+    pub fn label(&self) -> &str {
+        match self {
+            AstNodeVariant::Type => "type",
+            AstNodeVariant::Interface => "interface",
+            AstNodeVariant::Typedef => "typedef",
+            AstNodeVariant::Custom(label) => label,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct FoundDeclarationNode {
     pub ast_node_variant: AstNodeVariant,
     pub name: String,
@@ -17,9 +38,36 @@ pub struct FoundDeclarationNode {
     pub span_end: usize,
     pub is_exported: bool,
     pub body: String,
+    /// Names of other declarations the body refers to, in order of appearance.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+}
+
+impl FoundDeclarationNode {
+    /// Declared in a `.d.ts` (or `.d.mts`/`.d.cts`) file.
+    pub fn is_ambient(&self) -> bool {
+        is_declaration_file(Path::new(&self.filename))
+    }
+
+    /// Declared in a code fence of a Markdown or MDX document.
+    pub fn is_documented(&self) -> bool {
+        is_doc_file(Path::new(&self.filename))
+    }
+}
+
+/// A class naming an interface in its `implements` clause.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Implementation {
+    pub class_name: String,
+    /// The interface as written in the clause, i.e. its local name in `filename`.
+    pub interface: String,
+    pub filename: String,
+    pub line: usize,
+    pub col: usize,
 }
 
 pub trait DeclarationChecker {
+    #[allow(clippy::wrong_self_convention)]
     fn from_ast(
         &self,
         source: &str,
@@ -29,6 +77,19 @@ pub trait DeclarationChecker {
     ) -> FoundDeclarationNode;
 }
 
+/// Hook for declarations the built-in checkers don't cover (enums, namespaces, ...).
+///
+/// Returns a checker for `declaration` if this factory handles it.
+pub trait CheckerFactory {
+    fn checker<'a>(
+        &self,
+        declaration: &'a Declaration<'a>,
+    ) -> Option<Box<dyn DeclarationChecker + 'a>>;
+}
+
+/// 1-based line and column of `offset`, walking `source` from the start. Fine
+/// for a one-off lookup; use [`LineIndex`](crate::line_index::LineIndex) for
+/// many offsets in the same source.
 pub fn byte_offset_to_line_col(source: &str, offset: usize) -> (usize, usize) {
     let mut line = 1;
     let mut col = 1;
@@ -0,0 +1,200 @@
+use serde::Serialize;
+use std::fmt::Write;
+use std::path::Path;
+use std::time::Duration;
+use tta::landscape::Landscape;
+
+/// How `--stats` is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StatsFormat {
+    /// Aligned tables on stderr, below the report.
+    Table,
+    /// A single JSON object on stdout, unless `--stats-output` is given.
+    Json,
+}
+
+/// What the scan loop read and how long analysing it took (parsing and
+/// indexing every file).
+#[derive(Debug, Default, Serialize)]
+pub struct ScanStats {
+    pub files_scanned: usize,
+    pub files_skipped: usize,
+    pub analysis_time_ms: f64,
+}
+
+impl ScanStats {
+    pub fn new(files_scanned: usize, files_skipped: usize, analysis_time: Duration) -> Self {
+        ScanStats {
+            files_scanned,
+            files_skipped,
+            analysis_time_ms: analysis_time.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+/// What a scan read and what it kept around for the report.
+#[derive(Debug, Serialize)]
+pub struct MemoryStats {
+    pub files_read: usize,
+    pub bytes_read: usize,
+    /// Sources re-read because a diagnostic points into them.
+    pub files_kept: usize,
+    pub bytes_kept: usize,
+    /// Peak resident set size, where the platform reports it.
+    pub peak: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub scan: ScanStats,
+    pub memory: MemoryStats,
+    pub landscape: Landscape,
+}
+
+/// Prints `stats` in `format`, or writes them to `output` when given.
+pub fn print_stats(stats: &Stats, format: StatsFormat, output: Option<&Path>) {
+    let rendered = match format {
+        StatsFormat::Json => match serde_json::to_string_pretty(stats) {
+            Ok(json) => json + "\n",
+            Err(err) => {
+                eprintln!("Failed to serialize statistics: {}", err);
+                return;
+            }
+        },
+        StatsFormat::Table => {
+            let mut table = String::new();
+            write_scan_stats(&mut table, &stats.scan);
+            write_landscape(&mut table, &stats.landscape);
+            write_memory_stats(&mut table, &stats.memory);
+            table
+        }
+    };
+    match (output, format) {
+        (Some(path), _) => {
+            if let Err(err) = std::fs::write(path, rendered.trim_start()) {
+                eprintln!("Failed to write {}: {}", path.display(), err);
+            }
+        }
+        (None, StatsFormat::Json) => print!("{}", rendered),
+        (None, StatsFormat::Table) => eprint!("{}", rendered),
+    }
+}
+
+fn write_scan_stats(out: &mut String, scan: &ScanStats) {
+    let _ = writeln!(out, "\nScan:");
+    let _ = writeln!(out, "  Files scanned: {}", scan.files_scanned);
+    let _ = writeln!(out, "  Files skipped: {}", scan.files_skipped);
+    let _ = writeln!(out, "  Analysis time: {:.1} ms", scan.analysis_time_ms);
+}
+
+fn write_landscape(out: &mut String, landscape: &Landscape) {
+    let _ = writeln!(out, "\nDeclarations: {}", landscape.declarations);
+    let _ = writeln!(
+        out,
+        "  {:<12} {:>8} {:>8} {:>8}",
+        "kind", "total", "exported", "local"
+    );
+    for (kind, totals) in &landscape.variants {
+        let _ = writeln!(
+            out,
+            "  {:<12} {:>8} {:>8} {:>8}",
+            kind, totals.total, totals.exported, totals.local
+        );
+    }
+
+    if !landscape.largest.is_empty() {
+        let _ = writeln!(out, "\nLargest declarations:");
+        let _ = writeln!(out, "  {:>8}  declaration", "bytes");
+        for size in &landscape.largest {
+            let _ = writeln!(
+                out,
+                "  {:>8}  {} {} ({}:{})",
+                size.bytes, size.kind, size.name, size.filename, size.line
+            );
+        }
+    }
+
+    if !landscape.most_duplicated.is_empty() {
+        let _ = writeln!(out, "\nMost duplicated names:");
+        let _ = writeln!(out, "  {:>6} {:>6}  name", "copies", "files");
+        for name in &landscape.most_duplicated {
+            let _ = writeln!(out, "  {:>6} {:>6}  {}", name.copies, name.files, name.name);
+        }
+    }
+
+    if !landscape.groups.is_empty() {
+        let _ = writeln!(out, "\nDuplication by directory or project:");
+        let _ = writeln!(
+            out,
+            "  {:>6} {:>12} {:>10}  group",
+            "ratio", "declarations", "duplicated"
+        );
+        for group in &landscape.groups {
+            let _ = writeln!(
+                out,
+                "  {:>5.1}% {:>12} {:>10}  {}",
+                group.ratio * 100.0,
+                group.declarations,
+                group.duplicated,
+                group.group
+            );
+        }
+    }
+}
+
+fn write_memory_stats(out: &mut String, stats: &MemoryStats) {
+    let _ = writeln!(out, "\nMemory:");
+    let _ = writeln!(
+        out,
+        "  Sources read:  {} file{} ({})",
+        stats.files_read,
+        if stats.files_read == 1 { "" } else { "s" },
+        format_bytes(stats.bytes_read)
+    );
+    let _ = writeln!(
+        out,
+        "  Sources kept:  {} file{} ({})",
+        stats.files_kept,
+        if stats.files_kept == 1 { "" } else { "s" },
+        format_bytes(stats.bytes_kept)
+    );
+    let _ = match stats.peak {
+        Some(peak) => writeln!(out, "  Peak resident: {}", format_bytes(peak)),
+        None => writeln!(out, "  Peak resident: unknown on this platform"),
+    };
+}
+
+/// The high-water mark of the process's resident memory (`VmHWM`), on Linux.
+pub fn peak_memory() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+}
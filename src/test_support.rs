@@ -0,0 +1,13 @@
+use tempfile::TempDir;
+
+/// A fresh temporary directory holding `files` as `(path, contents)`, removed
+/// again when dropped.
+pub fn temp_tree(files: &[(&str, &str)]) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (path, contents) in files {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+    dir
+}
@@ -0,0 +1,466 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::files::{absolute, is_component_file, is_ts_file, normalize, relative_to_cwd};
+
+/// A `tsconfig.json` with its `extends` chain applied. Patterns and paths are
+/// already resolved to absolute paths against the config that declared them;
+/// `path` itself is kept relative to the working directory when it is below it.
+#[derive(Debug, Clone, Default)]
+pub struct TsConfig {
+    pub path: PathBuf,
+    pub files: Option<Vec<PathBuf>>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    /// Referenced `tsconfig.json` files (`references` are not inherited).
+    pub references: Vec<PathBuf>,
+    /// Merged `compilerOptions`, with path-valued options left as written.
+    pub compiler_options: Map<String, Value>,
+    /// Directory each compiler option was declared in, to resolve path options.
+    pub compiler_option_dirs: Vec<(String, PathBuf)>,
+}
+
+/// One compilation unit: a config and the files the compiler would pick up.
+#[derive(Debug, Clone)]
+pub struct TsProject {
+    pub config: TsConfig,
+    pub files: Vec<String>,
+    include: GlobSet,
+    exclude: GlobSet,
+    explicit: HashSet<PathBuf>,
+}
+
+impl TsConfig {
+    /// Reads `path` (a `tsconfig.json` or a directory containing one) and
+    /// applies its `extends` chain.
+    pub fn load(path: &Path) -> io::Result<TsConfig> {
+        let mut seen = HashSet::new();
+        load_config(&config_file(path), &mut seen)
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        self.path.parent().map(normalize).unwrap_or_default()
+    }
+
+    /// The directory a path-valued compiler option such as `baseUrl` or `outDir`
+    /// is relative to: the config that declared it.
+    pub fn option_dir(&self, option: &str) -> PathBuf {
+        self.compiler_option_dirs
+            .iter()
+            .rev()
+            .find(|(name, _)| name == option)
+            .map(|(_, dir)| dir.clone())
+            .unwrap_or_else(|| absolute(&self.dir()))
+    }
+
+    /// A path-valued compiler option resolved against its declaring config.
+    pub fn option_path(&self, option: &str) -> Option<PathBuf> {
+        let value = self.compiler_options.get(option)?.as_str()?;
+        Some(normalize(&self.option_dir(option).join(value)))
+    }
+}
+
+impl TsProject {
+    pub fn from_config(config: TsConfig) -> TsProject {
+        let dir = absolute(&config.dir());
+
+        let include = match (&config.include, &config.files) {
+            (Some(include), _) => include.clone(),
+            (None, Some(_)) => Vec::new(),
+            (None, None) => vec![pattern(&dir, "**/*")],
+        };
+        let exclude = config.exclude.clone().unwrap_or_else(|| {
+            let mut exclude: Vec<String> = ["node_modules", "bower_components", "jspm_packages"]
+                .iter()
+                .map(|default| pattern(&dir, default))
+                .collect();
+            if let Some(out_dir) = config.option_path("outDir") {
+                exclude.push(out_dir.to_string_lossy().to_string());
+            }
+            exclude
+        });
+
+        let explicit: HashSet<PathBuf> = config.files.iter().flatten().cloned().collect();
+        let mut project = TsProject {
+            files: Vec::new(),
+            include: glob_set(&include),
+            exclude: glob_set(&exclude),
+            explicit,
+            config,
+        };
+
+        let mut files: Vec<String> = project
+            .config
+            .files
+            .iter()
+            .flatten()
+            .filter(|file| file.is_file())
+            .map(|file| relative_to_cwd(file).to_string_lossy().to_string())
+            .collect();
+
+        let mut roots: Vec<PathBuf> = include.iter().map(|p| walk_root(p)).collect();
+        roots.sort();
+        roots.dedup();
+        let mut seen: HashSet<String> = files.iter().cloned().collect();
+        for root in roots {
+            let exclude = project.exclude.clone();
+            for entry in WalkBuilder::new(&root)
+                .standard_filters(false)
+                .hidden(true)
+                // Skip excluded folders such as node_modules without walking them:
+                // a folder is excluded when anything inside it would be.
+                .filter_entry(move |entry| !exclude.is_match(normalize(entry.path()).join("_")))
+                .build()
+                .filter_map(Result::ok)
+            {
+                let path = normalize(entry.path());
+                if project.contains(&path) {
+                    let file = relative_to_cwd(&path).to_string_lossy().to_string();
+                    if seen.insert(file.clone()) {
+                        files.push(file);
+                    }
+                }
+            }
+        }
+
+        project.files = files;
+        project
+    }
+
+    /// Whether the compiler would pick up `path` for this project.
+    pub fn contains(&self, path: &Path) -> bool {
+        let path = absolute(path);
+        if self.explicit.contains(&path) {
+            return true;
+        }
+        (is_ts_file(&path) || is_component_file(&path))
+            && self.include.is_match(&path)
+            && !self.exclude.is_match(&path)
+    }
+}
+
+/// Loads `path` and every project it references, depth first, each once.
+pub fn resolve_projects(path: &Path) -> io::Result<Vec<TsProject>> {
+    let mut projects = Vec::new();
+    let mut visited = HashSet::new();
+    collect_projects(&config_file(path), &mut visited, &mut projects)?;
+    Ok(projects)
+}
+
+fn collect_projects(
+    path: &Path,
+    visited: &mut HashSet<PathBuf>,
+    projects: &mut Vec<TsProject>,
+) -> io::Result<()> {
+    if !visited.insert(path.to_path_buf()) {
+        return Ok(());
+    }
+    let config = TsConfig::load(path)?;
+    let references = config.references.clone();
+    projects.push(TsProject::from_config(config));
+    for reference in references {
+        collect_projects(&reference, visited, projects)?;
+    }
+    Ok(())
+}
+
+fn config_file(path: &Path) -> PathBuf {
+    let path = normalize(path);
+    if path.as_os_str().is_empty() || path.is_dir() {
+        path.join("tsconfig.json")
+    } else {
+        path
+    }
+}
+
+fn load_config(path: &Path, seen: &mut HashSet<PathBuf>) -> io::Result<TsConfig> {
+    if !seen.insert(path.to_path_buf()) {
+        return Err(io::Error::other(format!(
+            "circular extends in {}",
+            path.display()
+        )));
+    }
+
+    let contents = std::fs::read_to_string(path)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+    let json: Value = serde_json::from_str(&strip_jsonc(&contents))
+        .map_err(|err| io::Error::other(format!("{}: {}", path.display(), err)))?;
+    let dir = path.parent().map(absolute).unwrap_or_default();
+
+    // `extends` may be a single config or, since TS 5.0, a list applied in order.
+    let bases: Vec<&str> = match json.get("extends") {
+        Some(Value::String(base)) => vec![base],
+        Some(Value::Array(bases)) => bases.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    let mut config = TsConfig::default();
+    for base in bases {
+        let base_path = resolve_extends(&dir, base).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{}: cannot resolve extends '{}'", path.display(), base),
+            )
+        })?;
+        let base = load_config(&base_path, seen)?;
+        // Only a cycle is an error; two bases may share a common ancestor.
+        seen.remove(&base_path);
+        config.files = base.files.or(config.files);
+        config.include = base.include.or(config.include);
+        config.exclude = base.exclude.or(config.exclude);
+        config.compiler_options.extend(base.compiler_options);
+        config
+            .compiler_option_dirs
+            .extend(base.compiler_option_dirs);
+    }
+
+    config.path = relative_to_cwd(&absolute(path));
+    config.references = Vec::new();
+
+    let patterns = |key: &str| -> Option<Vec<String>> {
+        json.get(key)?.as_array().map(|list| {
+            list.iter()
+                .filter_map(Value::as_str)
+                .map(|p| pattern(&dir, p))
+                .collect()
+        })
+    };
+    if let Some(include) = patterns("include") {
+        config.include = Some(include);
+    }
+    if let Some(exclude) = patterns("exclude") {
+        config.exclude = Some(exclude);
+    }
+    if let Some(files) = json.get("files").and_then(Value::as_array) {
+        config.files = Some(
+            files
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|file| normalize(&dir.join(file)))
+                .collect(),
+        );
+    }
+    if let Some(options) = json.get("compilerOptions").and_then(Value::as_object) {
+        for (name, value) in options {
+            config.compiler_options.insert(name.clone(), value.clone());
+            config
+                .compiler_option_dirs
+                .push((name.clone(), dir.clone()));
+        }
+    }
+    if let Some(references) = json.get("references").and_then(Value::as_array) {
+        config.references = references
+            .iter()
+            .filter_map(|reference| reference.get("path")?.as_str())
+            .map(|reference| config_file(&dir.join(reference)))
+            .collect();
+    }
+
+    Ok(config)
+}
+
+/// Resolves an `extends` value: a relative or absolute path, or a config
+/// shipped in a package under `node_modules`.
+fn resolve_extends(dir: &Path, base: &str) -> Option<PathBuf> {
+    let with_json = |path: PathBuf| {
+        if path.is_file() {
+            Some(path)
+        } else {
+            let mut json = path.into_os_string();
+            json.push(".json");
+            Some(PathBuf::from(json)).filter(|path| path.is_file())
+        }
+    };
+
+    if base.starts_with('.') || Path::new(base).is_absolute() {
+        return with_json(normalize(&dir.join(base)));
+    }
+
+    let canonical = std::fs::canonicalize(dir).ok()?;
+    canonical.ancestors().find_map(|ancestor| {
+        let package = ancestor.join("node_modules").join(base);
+        with_json(package.clone())
+            .or_else(|| Some(package.join("tsconfig.json")).filter(|p| p.is_file()))
+    })
+}
+
+/// Resolves a tsconfig `include`/`exclude` entry, written relative to the
+/// tsconfig's directory `dir`, to a glob joined onto `dir` (absolute, as the
+/// callers pass it). Entries without wildcards or an extension name a
+/// directory and match everything below it.
+fn pattern(dir: &Path, pattern: &str) -> String {
+    let last = pattern.rsplit('/').next().unwrap_or(pattern);
+    let is_directory =
+        last == "." || last == ".." || (!last.contains(['*', '?']) && !last.contains('.'));
+    let joined = normalize(&dir.join(pattern));
+    let joined = joined.to_string_lossy();
+    if is_directory {
+        if joined.is_empty() {
+            "**/*".to_string()
+        } else {
+            format!("{}/**/*", joined)
+        }
+    } else {
+        joined.to_string()
+    }
+}
+
+/// The literal directory prefix of a glob, where walking has to start.
+fn walk_root(pattern: &str) -> PathBuf {
+    let mut root = if pattern.starts_with('/') {
+        PathBuf::from("/")
+    } else {
+        PathBuf::new()
+    };
+    let segments: Vec<&str> = pattern.split('/').collect();
+    for (i, segment) in segments.iter().enumerate() {
+        if segment.contains(['*', '?', '[', '{']) || i == segments.len() - 1 {
+            break;
+        }
+        root.push(segment);
+    }
+    if root.as_os_str().is_empty() {
+        root.push(".");
+    }
+    root
+}
+
+fn glob_set(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        if let Ok(glob) = GlobBuilder::new(pattern).literal_separator(true).build() {
+            builder.add(glob);
+        }
+        // A pattern also excludes/includes everything below it when it names a directory.
+        if let Ok(glob) = GlobBuilder::new(&format!("{}/**", pattern))
+            .literal_separator(true)
+            .build()
+        {
+            builder.add(glob);
+        }
+    }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
+}
+
+/// Strips `//` and `/* */` comments and trailing commas, which `tsconfig.json`
+/// allows but JSON does not.
+pub fn strip_jsonc(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(ch) = chars.next() {
+        if in_string {
+            out.push(ch);
+            match ch {
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        out.push(escaped);
+                    }
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match ch {
+            '"' => {
+                in_string = true;
+                out.push(ch);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                for next in chars.by_ref() {
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            ']' | '}' => {
+                let trimmed = out.trim_end().len();
+                if out[..trimmed].ends_with(',') {
+                    out.truncate(trimmed - 1);
+                }
+                out.push(ch);
+            }
+            _ => out.push(ch),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_tree;
+
+    #[test]
+    fn test_strip_jsonc() {
+        let source =
+            "{\n  // comment\n  \"a\": \"//not a comment\", /* block */\n  \"b\": [1, 2,],\n}";
+        let json: Value = serde_json::from_str(&strip_jsonc(source)).unwrap();
+        assert_eq!(json["a"], "//not a comment");
+        assert_eq!(json["b"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_pattern_directory_and_glob() {
+        assert_eq!(pattern(Path::new("app"), "src"), "app/src/**/*");
+        assert_eq!(pattern(Path::new("app"), "src/**/*.ts"), "app/src/**/*.ts");
+        assert_eq!(pattern(Path::new(""), "."), "**/*");
+        assert_eq!(walk_root("app/src/**/*.ts"), PathBuf::from("app/src"));
+        assert_eq!(walk_root("/app/**/*"), PathBuf::from("/app"));
+        assert_eq!(walk_root("**/*"), PathBuf::from("."));
+    }
+
+    #[test]
+    fn test_resolve_projects_with_extends_and_references() {
+        let dir = temp_tree(
+            &[
+                (
+                    "tsconfig.base.json",
+                    r#"{ "compilerOptions": { "strict": true }, "exclude": ["**/*.spec.ts"] }"#,
+                ),
+                (
+                    "app/tsconfig.json",
+                    "{\n // app\n \"extends\": \"../tsconfig.base\",\n \"include\": [\"src\"],\n \"references\": [{ \"path\": \"../lib\" }],\n}",
+                ),
+                ("app/src/a.ts", "type A = string;"),
+                ("app/src/a.spec.ts", "type A = string;"),
+                ("app/other/b.ts", "type B = string;"),
+                ("lib/tsconfig.json", r#"{ "files": ["index.ts"] }"#),
+                ("lib/index.ts", "type L = string;"),
+                ("lib/unlisted.ts", "type U = string;"),
+            ],
+        );
+        let root = dir.path();
+
+        let projects = resolve_projects(&root.join("app")).unwrap();
+        assert_eq!(projects.len(), 2);
+
+        let app = &projects[0];
+        assert_eq!(app.config.compiler_options["strict"], true);
+        assert_eq!(app.files.len(), 1);
+        assert!(app.files[0].ends_with("app/src/a.ts"));
+
+        let lib = &projects[1];
+        assert_eq!(lib.files.len(), 1);
+        assert!(lib.files[0].ends_with("lib/index.ts"));
+        assert!(!lib.contains(&root.join("lib/unlisted.ts")));
+    }
+}
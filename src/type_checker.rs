@@ -1,9 +1,19 @@
-use oxc::ast::ast::{TSType, TSTypeAliasDeclaration};
-use oxc::span::Span;
+use oxc::ast::ast::{
+    Expression, FormalParameters, TSImportType, TSImportTypeQualifier, TSLiteral, TSMappedType,
+    TSMappedTypeModifierOperator, TSNamedTupleMember, TSTupleElement, TSType,
+    TSTypeAliasDeclaration, TSTypeAnnotation, TSTypeName, TSTypeParameter, TSTypePredicateName,
+    TSTypeQueryExprName, TSTypeReference,
+};
+use oxc::ast::{AstKind, AstType};
+use oxc::ast_visit::{walk, Visit};
+use oxc::span::{GetSpan, Span};
 
+use crate::interface_checker::serialize_interface_body;
+use crate::line_index::LineIndex;
 use crate::shared_lib::{
     byte_offset_to_line_col, AstNodeVariant, DeclarationChecker, FoundDeclarationNode,
 };
+use crate::usage::type_parameter_scope;
 
 pub struct TypeChecker<'a> {
     pub type_alias: &'a TSTypeAliasDeclaration<'a>,
@@ -17,14 +27,44 @@ impl<'a> DeclarationChecker for TypeChecker<'a> {
         is_exported: bool,
         override_span: Option<Span>,
     ) -> FoundDeclarationNode {
-        let name = self.type_alias.id.name.to_string();
+        let span = override_span.unwrap_or(self.type_alias.span);
+        let location = byte_offset_to_line_col(source, span.start as usize);
+        self.node(location, span, filename, is_exported)
+    }
+}
 
+impl TypeChecker<'_> {
+    /// [`DeclarationChecker::from_ast`], looking the location up in the line
+    /// index of the whole file instead of walking the source.
+    pub(crate) fn indexed(
+        &self,
+        lines: &LineIndex,
+        filename: &str,
+        is_exported: bool,
+        override_span: Option<Span>,
+    ) -> FoundDeclarationNode {
         let span = override_span.unwrap_or(self.type_alias.span);
+        self.node(
+            lines.line_col(span.start as usize),
+            span,
+            filename,
+            is_exported,
+        )
+    }
+
+    fn node(
+        &self,
+        (line, col): (usize, usize),
+        span: Span,
+        filename: &str,
+        is_exported: bool,
+    ) -> FoundDeclarationNode {
+        let name = self.type_alias.id.name.to_string();
         let start = span.start as usize;
         let end = span.end as usize;
-
-        let (line, col) = byte_offset_to_line_col(source, start);
         let body = serialize_ts_type(&self.type_alias.type_annotation);
+        let mut references = ReferenceCollector::default();
+        references.visit_ts_type_alias_declaration(self.type_alias);
 
         FoundDeclarationNode {
             ast_node_variant: AstNodeVariant::Type,
@@ -36,6 +76,7 @@ impl<'a> DeclarationChecker for TypeChecker<'a> {
             span_start: start,
             span_end: end,
             is_exported,
+            references: references.finish(),
         }
     }
 }
@@ -58,19 +99,14 @@ pub fn serialize_ts_type(ts_type: &TSType) -> String {
         TSType::TSThisType(_) => "this".to_string(),
 
         TSType::TSTypeReference(r) => {
-            let name = format!("{:?}", r.type_name);
-            if let Some(params) = &r.type_arguments {
-                let ps: Vec<String> = params.params.iter().map(|p| serialize_ts_type(p)).collect();
-                format!("{}<{}>", name, ps.join(", "))
-            } else {
-                name
+            let name = r.type_name.to_string();
+            match &r.type_arguments {
+                Some(params) => format!("{}{}", name, serialize_type_arguments(&params.params)),
+                None => name,
             }
         }
 
-        TSType::TSTypeLiteral(lit) => {
-            let members: Vec<String> = lit.members.iter().map(|m| format!("{:?}", m)).collect();
-            format!("{{ {} }}", members.join("; "))
-        }
+        TSType::TSTypeLiteral(lit) => serialize_interface_body(&lit.members),
 
         TSType::TSUnionType(u) => {
             let types: Vec<String> = u.types.iter().map(|t| serialize_ts_type(t)).collect();
@@ -85,12 +121,25 @@ pub fn serialize_ts_type(ts_type: &TSType) -> String {
         TSType::TSArrayType(a) => format!("{}[]", serialize_ts_type(&a.element_type)),
 
         TSType::TSTupleType(t) => {
-            let elems: Vec<String> = t.element_types.iter().map(|e| format!("{:?}", e)).collect();
+            let elems: Vec<String> = t
+                .element_types
+                .iter()
+                .map(serialize_tuple_element)
+                .collect();
             format!("[{}]", elems.join(", "))
         }
 
-        TSType::TSFunctionType(f) => format!("FunctionType({:?})", f.params),
-        TSType::TSConstructorType(c) => format!("ConstructorType({:?})", c.params),
+        TSType::TSFunctionType(f) => format!(
+            "{} => {}",
+            serialize_params(&f.params),
+            serialize_ts_type(&f.return_type.type_annotation)
+        ),
+        TSType::TSConstructorType(c) => format!(
+            "{}new {} => {}",
+            if c.r#abstract { "abstract " } else { "" },
+            serialize_params(&c.params),
+            serialize_ts_type(&c.return_type.type_annotation)
+        ),
         TSType::TSConditionalType(c) => {
             format!(
                 "{} extends {} ? {} : {}",
@@ -100,7 +149,23 @@ pub fn serialize_ts_type(ts_type: &TSType) -> String {
                 serialize_ts_type(&c.false_type)
             )
         }
-        TSType::TSTypeQuery(q) => format!("typeof {:?}", q.expr_name),
+        TSType::TSTypeQuery(q) => {
+            let name = match &q.expr_name {
+                TSTypeQueryExprName::TSImportType(i) => serialize_import_type(i),
+                other => other
+                    .as_ts_type_name()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+            };
+            match &q.type_arguments {
+                Some(params) => format!(
+                    "typeof {}{}",
+                    name,
+                    serialize_type_arguments(&params.params)
+                ),
+                None => format!("typeof {}", name),
+            }
+        }
         TSType::TSIndexedAccessType(i) => {
             format!(
                 "{}[{}]",
@@ -108,24 +173,262 @@ pub fn serialize_ts_type(ts_type: &TSType) -> String {
                 serialize_ts_type(&i.index_type)
             )
         }
-        TSType::TSMappedType(m) => format!("MappedType({:?})", m.key),
+        TSType::TSMappedType(m) => {
+            let modifier = |op: &Option<TSMappedTypeModifierOperator>, text: &str| match op {
+                Some(TSMappedTypeModifierOperator::True) => text.to_string(),
+                Some(TSMappedTypeModifierOperator::Plus) => format!("+{}", text),
+                Some(TSMappedTypeModifierOperator::Minus) => format!("-{}", text),
+                None => String::new(),
+            };
+            let readonly = modifier(&m.readonly, "readonly ");
+            let optional = modifier(&m.optional, "?");
+            let name_type = m
+                .name_type
+                .as_ref()
+                .map(|t| format!(" as {}", serialize_ts_type(t)))
+                .unwrap_or_default();
+            let value = m
+                .type_annotation
+                .as_ref()
+                .map(serialize_ts_type)
+                .unwrap_or_else(|| "any".to_string());
+            format!(
+                "{{ {}[{} in {}{}]{}: {} }}",
+                readonly,
+                m.key.name,
+                serialize_ts_type(&m.constraint),
+                name_type,
+                optional,
+                value
+            )
+        }
         TSType::TSTypeOperatorType(o) => {
-            format!("{:?} {}", o.operator, serialize_ts_type(&o.type_annotation))
+            format!(
+                "{} {}",
+                o.operator.to_str(),
+                serialize_ts_type(&o.type_annotation)
+            )
         }
-        TSType::TSImportType(i) => format!("import({:?})", i.source),
+        TSType::TSImportType(i) => serialize_import_type(i),
         TSType::TSParenthesizedType(p) => {
             format!("({})", serialize_ts_type(&p.type_annotation))
         }
-        TSType::TSInferType(i) => format!("infer {:?}", i.type_parameter),
-        TSType::TSLiteralType(l) => format!("{:?}", l.literal),
-        TSType::TSTemplateLiteralType(t) => format!("TemplateLiteral({:?})", t.quasis),
-        TSType::TSNamedTupleMember(m) => {
-            format!("{}: {:?}", m.label, m.element_type)
+        TSType::TSInferType(i) => format!("infer {}", serialize_type_parameter(&i.type_parameter)),
+        TSType::TSLiteralType(l) => serialize_literal(&l.literal),
+        TSType::TSTemplateLiteralType(t) => {
+            let mut out = String::from("`");
+            for (i, quasi) in t.quasis.iter().enumerate() {
+                out.push_str(&quasi.value.raw);
+                if let Some(ty) = t.types.get(i) {
+                    out.push_str(&format!("${{{}}}", serialize_ts_type(ty)));
+                }
+            }
+            out.push('`');
+            out
         }
+        TSType::TSNamedTupleMember(m) => serialize_named_tuple_member(m),
         TSType::JSDocNullableType(n) => format!("?{}", serialize_ts_type(&n.type_annotation)),
         TSType::JSDocNonNullableType(n) => format!("!{}", serialize_ts_type(&n.type_annotation)),
         TSType::JSDocUnknownType(_) => "unknown(jsdoc)".to_string(),
-        TSType::TSTypePredicate(p) => format!("TypePredicate({:?})", p.parameter_name),
+        TSType::TSTypePredicate(p) => {
+            let name = match &p.parameter_name {
+                TSTypePredicateName::Identifier(id) => id.name.to_string(),
+                TSTypePredicateName::This(_) => "this".to_string(),
+            };
+            let asserts = if p.asserts { "asserts " } else { "" };
+            match &p.type_annotation {
+                Some(ta) => format!(
+                    "{}{} is {}",
+                    asserts,
+                    name,
+                    serialize_ts_type(&ta.type_annotation)
+                ),
+                None => format!("{}{}", asserts, name),
+            }
+        }
+    }
+}
+
+fn serialize_type_arguments(params: &[TSType]) -> String {
+    let ps: Vec<String> = params.iter().map(serialize_ts_type).collect();
+    format!("<{}>", ps.join(", "))
+}
+
+fn serialize_type_parameter(param: &TSTypeParameter) -> String {
+    let mut out = param.name.name.to_string();
+    if let Some(constraint) = &param.constraint {
+        out.push_str(&format!(" extends {}", serialize_ts_type(constraint)));
+    }
+    if let Some(default) = &param.default {
+        out.push_str(&format!(" = {}", serialize_ts_type(default)));
+    }
+    out
+}
+
+fn serialize_tuple_element(element: &TSTupleElement) -> String {
+    match element {
+        TSTupleElement::TSOptionalType(o) => format!("{}?", serialize_ts_type(&o.type_annotation)),
+        TSTupleElement::TSRestType(r) => format!("...{}", serialize_ts_type(&r.type_annotation)),
+        other => other
+            .as_ts_type()
+            .map(serialize_ts_type)
+            .unwrap_or_default(),
+    }
+}
+
+fn serialize_named_tuple_member(member: &TSNamedTupleMember) -> String {
+    format!(
+        "{}{}: {}",
+        member.label.name,
+        if member.optional { "?" } else { "" },
+        serialize_tuple_element(&member.element_type)
+    )
+}
+
+/// `(a: string, b?: number, ...rest: T[])`
+pub(crate) fn serialize_params(params: &FormalParameters) -> String {
+    let annotation = |ta: &Option<oxc::allocator::Box<TSTypeAnnotation>>| {
+        ta.as_ref()
+            .map(|ta| format!(": {}", serialize_ts_type(&ta.type_annotation)))
+            .unwrap_or_default()
+    };
+    let mut parts: Vec<String> = params
+        .items
+        .iter()
+        .map(|param| {
+            let name = param
+                .pattern
+                .get_identifier_name()
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| "_".to_string());
+            format!(
+                "{}{}{}",
+                name,
+                if param.optional { "?" } else { "" },
+                annotation(&param.type_annotation)
+            )
+        })
+        .collect();
+    if let Some(rest) = &params.rest {
+        let name = rest
+            .rest
+            .argument
+            .get_identifier_name()
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| "_".to_string());
+        parts.push(format!("...{}{}", name, annotation(&rest.type_annotation)));
+    }
+    format!("({})", parts.join(", "))
+}
+
+fn serialize_import_type(import: &TSImportType) -> String {
+    let mut out = format!("import({:?})", import.source.value.as_str());
+    let mut qualifier = import.qualifier.as_ref();
+    let mut names = Vec::new();
+    while let Some(q) = qualifier {
+        match q {
+            TSImportTypeQualifier::Identifier(id) => {
+                names.push(id.name.to_string());
+                qualifier = None;
+            }
+            TSImportTypeQualifier::QualifiedName(qualified) => {
+                names.push(qualified.right.name.to_string());
+                qualifier = Some(&qualified.left);
+            }
+        }
+    }
+    for name in names.iter().rev() {
+        out.push('.');
+        out.push_str(name);
+    }
+    if let Some(params) = &import.type_arguments {
+        out.push_str(&serialize_type_arguments(&params.params));
+    }
+    out
+}
+
+fn serialize_literal(literal: &TSLiteral) -> String {
+    match literal {
+        TSLiteral::BooleanLiteral(b) => b.value.to_string(),
+        TSLiteral::NumericLiteral(n) => n.value.to_string(),
+        TSLiteral::BigIntLiteral(b) => b
+            .raw
+            .as_ref()
+            .map(|raw| raw.to_string())
+            .unwrap_or_default(),
+        TSLiteral::StringLiteral(s) => format!("{:?}", s.value.as_str()),
+        TSLiteral::TemplateLiteral(t) => {
+            let raw: Vec<&str> = t.quasis.iter().map(|q| q.value.raw.as_str()).collect();
+            format!("`{}`", raw.join(""))
+        }
+        TSLiteral::UnaryExpression(u) => match &u.argument {
+            Expression::NumericLiteral(n) => format!("{}{}", u.operator.as_str(), n.value),
+            Expression::BigIntLiteral(b) => format!(
+                "{}{}",
+                u.operator.as_str(),
+                b.raw
+                    .as_ref()
+                    .map(|raw| raw.to_string())
+                    .unwrap_or_default()
+            ),
+            _ => u.operator.as_str().to_string(),
+        },
+    }
+}
+
+/// Collects the names of the declarations a type refers to, leaving out type
+/// parameters and other names bound inside it where they are in scope.
+#[derive(Default)]
+pub struct ReferenceCollector {
+    references: Vec<(String, Span)>,
+    /// The nodes currently being visited, innermost last.
+    ancestors: Vec<(AstType, Span)>,
+    /// Bound names with the span they are in scope for.
+    bound: Vec<(String, Span)>,
+}
+
+impl ReferenceCollector {
+    pub fn finish(self) -> Vec<String> {
+        let is_bound = |name: &str, span: Span| {
+            self.bound.iter().any(|(bound, scope)| {
+                bound == name && scope.start <= span.start && span.end <= scope.end
+            })
+        };
+        let mut names: Vec<String> = Vec::new();
+        for (name, span) in &self.references {
+            if !is_bound(name, *span) && !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+}
+
+impl<'a> Visit<'a> for ReferenceCollector {
+    fn enter_node(&mut self, kind: AstKind<'a>) {
+        self.ancestors.push((kind.ty(), kind.span()));
+    }
+
+    fn leave_node(&mut self, _kind: AstKind<'a>) {
+        self.ancestors.pop();
+    }
+
+    fn visit_ts_type_reference(&mut self, it: &TSTypeReference<'a>) {
+        if let TSTypeName::IdentifierReference(id) = &it.type_name {
+            self.references.push((id.name.to_string(), it.span));
+        }
+        walk::walk_ts_type_reference(self, it);
+    }
+
+    fn visit_ts_type_parameter(&mut self, it: &TSTypeParameter<'a>) {
+        let scope = type_parameter_scope(&self.ancestors).unwrap_or(it.span);
+        self.bound.push((it.name.name.to_string(), scope));
+        walk::walk_ts_type_parameter(self, it);
+    }
+
+    fn visit_ts_mapped_type(&mut self, it: &TSMappedType<'a>) {
+        self.bound.push((it.key.name.to_string(), it.span));
+        walk::walk_ts_mapped_type(self, it);
     }
 }
 
@@ -202,6 +505,59 @@ mod tests {
         assert_ne!(a.body, b.body);
     }
 
+    #[test]
+    fn test_type_checker_bodies_ignore_position() {
+        let a = parse_type("type Foo = { a: 'x'; b: Bar<[number, string?]> };");
+        let b = parse_type("\n\n    type Foo = { a: 'x'; b: Bar<[number, string?]> };");
+        assert_eq!(a.body, b.body);
+        assert_eq!(a.body, "{ a: \"x\"; b: Bar<[number, string?]> }");
+        assert_eq!(a.references, vec!["Bar"]);
+    }
+
+    #[test]
+    fn test_type_checker_function_and_mapped_types() {
+        let node =
+            parse_type("type Fn<T> = (a: T, ...rest: string[]) => { [K in keyof T]?: T[K] };");
+        assert_eq!(
+            node.body,
+            "(a: T, ...rest: string[]) => { [K in keyof T]?: T[K] }"
+        );
+        assert!(node.references.is_empty());
+    }
+
+    #[test]
+    fn test_type_checker_bodies_ignore_formatting() {
+        let a = parse_type("type Foo = { a: string, b?: number };");
+        let b = parse_type("type Foo = {\n  // the a\n  a: string;\n  b?: number;\n};");
+        assert_eq!(a.body, b.body);
+        assert_ne!(
+            a.body,
+            parse_type("type Foo = { a: string; b: number };").body
+        );
+    }
+
+    #[test]
+    fn test_type_checker_conditional_and_template_types() {
+        let node = parse_type(
+            "type Foo<T> = T extends Array<infer U extends string> ? `id-${U}` : never;",
+        );
+        assert_eq!(
+            node.body,
+            "T extends Array<infer U extends string> ? `id-${U}` : never"
+        );
+    }
+
+    #[test]
+    fn test_type_checker_import_and_query_types() {
+        let node = parse_type(
+            "type Foo = [first: typeof config, rest?: import('./models').User<string>];",
+        );
+        assert_eq!(
+            node.body,
+            "[first: typeof config, rest?: import(\"./models\").User<string>]"
+        );
+    }
+
     #[test]
     fn test_type_checker_line_col() {
         let node = parse_type("type Foo = string;");
@@ -222,4 +578,56 @@ mod tests {
         let node = parse_type("type Foo = string;");
         assert_eq!(node.filename, "test.ts");
     }
+
+    #[test]
+    fn test_structured_bodies_decide_severity_without_deep() {
+        use crate::{Analyzer, Severity};
+
+        let severity = |a: &str, b: &str| {
+            let mut analyzer = Analyzer::new();
+            analyzer.add_source("a.ts", a);
+            analyzer.add_source("b.ts", b);
+            let findings = analyzer.findings();
+            assert_eq!(findings.len(), 1);
+            findings[0].severity
+        };
+
+        // Copies at different offsets compare by structure, not by position.
+        assert_eq!(
+            severity(
+                "type Foo = { a: string; b?: number };",
+                "\n\n  type Foo = { a: string, b?: number };"
+            ),
+            Severity::Critical
+        );
+        assert_eq!(
+            severity(
+                "type Fn = (a: string) => void;",
+                "type Other = 1;\ntype Fn = (a: string) => void;"
+            ),
+            Severity::Critical
+        );
+        assert_eq!(
+            severity(
+                "type Pair = [string, number?];",
+                "\ntype Pair = [string, number?];"
+            ),
+            Severity::Critical
+        );
+        // Return types and parameter names are part of a function type.
+        assert_eq!(
+            severity(
+                "type Fn = (a: string) => void;",
+                "type Fn = (a: string) => boolean;"
+            ),
+            Severity::Warning
+        );
+        assert_eq!(
+            severity(
+                "type Fn = (a: string) => void;",
+                "type Fn = (b: string) => void;"
+            ),
+            Severity::Warning
+        );
+    }
 }
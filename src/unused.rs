@@ -0,0 +1,193 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::analyzer::resolve_usage;
+use crate::imports::ImportGraph;
+use crate::shared_lib::FoundDeclarationNode;
+use crate::usage::Usage;
+
+/// Why a declaration counts as dead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum UnusedKind {
+    /// Nothing in the scanned set refers to it.
+    Unreferenced,
+    /// Exported and used in its own file, but no other file imports it.
+    NotImported,
+}
+
+/// A declaration no scanned file needs.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnusedDeclaration {
+    pub kind: UnusedKind,
+    pub declaration: FoundDeclarationNode,
+}
+
+/// Every declaration that no usage site resolves to and no file imports or
+/// re-exports, and every exported one that is only used inside its own file,
+/// ordered by location.
+///
+/// Usages that stay ambiguous keep every declaration of that name alive, so a
+/// copy is never reported just because it could not be told apart from
+/// another.
+pub fn find_unused(
+    results: &HashMap<String, Vec<FoundDeclarationNode>>,
+    usages: &[Usage],
+    imports: Option<&ImportGraph>,
+) -> Vec<UnusedDeclaration> {
+    let mut used_locally: HashSet<(&str, usize)> = HashSet::new();
+    let mut used_elsewhere: HashSet<(&str, usize)> = HashSet::new();
+    let mut ambiguous: HashSet<&str> = HashSet::new();
+
+    for usage in usages {
+        let Some(declaration) = resolve_usage(results, imports, usage) else {
+            ambiguous.insert(usage.name.as_str());
+            continue;
+        };
+        let key = (declaration.filename.as_str(), declaration.span_start);
+        if declaration.filename != usage.filename {
+            used_elsewhere.insert(key);
+        } else if !(declaration.span_start..declaration.span_end).contains(&usage.span_start) {
+            // A recursive type referring to itself does not keep it alive.
+            used_locally.insert(key);
+        }
+    }
+
+    // Importing or re-exporting a declaration keeps it alive even where the
+    // importing file never uses it, as with barrel files.
+    let imported = |declaration: &FoundDeclarationNode| {
+        imports.is_some_and(|graph| {
+            graph
+                .importers(&declaration.filename, &declaration.name)
+                .keys()
+                .any(|module| *module != declaration.filename)
+        })
+    };
+
+    let mut unused = Vec::new();
+    for (name, declarations) in results {
+        if ambiguous.contains(name.as_str()) {
+            continue;
+        }
+        for declaration in declarations {
+            if declaration.is_documented() {
+                continue;
+            }
+            let key = (declaration.filename.as_str(), declaration.span_start);
+            let kind = if used_elsewhere.contains(&key) || imported(declaration) {
+                continue;
+            } else if !used_locally.contains(&key) {
+                UnusedKind::Unreferenced
+            } else if declaration.is_exported {
+                UnusedKind::NotImported
+            } else {
+                continue;
+            };
+            unused.push(UnusedDeclaration {
+                kind,
+                declaration: declaration.clone(),
+            });
+        }
+    }
+    unused.sort_by(|a, b| {
+        (&a.declaration.filename, a.declaration.span_start)
+            .cmp(&(&b.declaration.filename, b.declaration.span_start))
+    });
+    unused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Analyzer;
+
+    fn unused(analyzer: &Analyzer) -> Vec<(String, String, UnusedKind)> {
+        analyzer
+            .unused()
+            .into_iter()
+            .map(|u| (u.declaration.filename, u.declaration.name, u.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_reports_unreferenced_and_not_imported() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source(
+            "a.ts",
+            "export interface Used { x: string }\n\
+             export interface LocalOnly { x: string }\n\
+             interface Dead { next: Dead }\n\
+             const l: LocalOnly = { x: '' };",
+        );
+        analyzer.add_source(
+            "b.ts",
+            "import { Used } from './a';\nconst u: Used = { x: '' };",
+        );
+
+        assert_eq!(
+            unused(&analyzer),
+            vec![
+                (
+                    "a.ts".to_string(),
+                    "LocalOnly".to_string(),
+                    UnusedKind::NotImported
+                ),
+                (
+                    "a.ts".to_string(),
+                    "Dead".to_string(),
+                    UnusedKind::Unreferenced
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dead_duplicate_reported_per_copy() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a.ts", "export interface IFoo { x: string }");
+        analyzer.add_source("b.ts", "export interface IFoo { x: string }");
+        analyzer.add_source(
+            "use.ts",
+            "import { IFoo } from './b';\nconst f: IFoo = { x: '' };",
+        );
+
+        assert_eq!(
+            unused(&analyzer),
+            vec![(
+                "a.ts".to_string(),
+                "IFoo".to_string(),
+                UnusedKind::Unreferenced
+            )]
+        );
+    }
+
+    #[test]
+    fn test_imports_and_reexports_count_as_references() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("lib/pub.ts", "export interface Pub { x: string }");
+        analyzer.add_source("lib/hidden.ts", "export interface Hidden { x: string }");
+        analyzer.add_source(
+            "lib/index.ts",
+            "export * from './pub';\nexport { Pub as Alias } from './pub';",
+        );
+        analyzer.add_source("app.ts", "import { Pub } from './lib';");
+
+        assert_eq!(
+            unused(&analyzer),
+            vec![(
+                "lib/hidden.ts".to_string(),
+                "Hidden".to_string(),
+                UnusedKind::Unreferenced
+            )]
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_usage_keeps_all_copies() {
+        let mut analyzer = Analyzer::new();
+        analyzer.add_source("a.ts", "interface IFoo { x: string }");
+        analyzer.add_source("b.ts", "interface IFoo { x: string }");
+        analyzer.add_source("use.ts", "const f: IFoo = { x: '' };");
+
+        assert!(unused(&analyzer).is_empty());
+    }
+}
@@ -0,0 +1,275 @@
+use oxc::ast::ast::{
+    Expression, Program, TSAsExpression, TSClassImplements, TSInterfaceHeritage, TSMappedType,
+    TSSatisfiesExpression, TSTypeAssertion, TSTypeName, TSTypeParameter, TSTypeReference,
+};
+use oxc::ast::{AstKind, AstType};
+use oxc::ast_visit::{walk, Visit};
+use oxc::span::{GetSpan, Span};
+use serde::Serialize;
+
+use crate::line_index::LineIndex;
+
+/// Where a type name is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum UsageKind {
+    /// Annotations, generic arguments and any other type position.
+    Type,
+    /// `class X implements IFoo`
+    Implements,
+    /// `interface X extends IFoo`
+    Extends,
+    /// `value satisfies IFoo`
+    Satisfies,
+    /// `value as IFoo` or `<IFoo>value`
+    Assertion,
+}
+
+/// One site referring to a type by name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Usage {
+    /// The referenced name, without any qualifier.
+    pub name: String,
+    /// `ns` in `ns.IFoo`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qualifier: Option<String>,
+    pub kind: UsageKind,
+    pub filename: String,
+    pub line: usize,
+    pub col: usize,
+    pub span_start: usize,
+    pub span_end: usize,
+}
+
+/// Collects every type reference in `program`, skipping references to type
+/// parameters within the declaration that introduces them.
+pub fn collect_usages(program: &Program, lines: &LineIndex, filename: &str) -> Vec<Usage> {
+    let mut collector = UsageCollector {
+        lines,
+        filename,
+        kind: UsageKind::Type,
+        usages: Vec::new(),
+        ancestors: Vec::new(),
+        type_parameters: Vec::new(),
+    };
+    collector.visit_program(program);
+
+    let type_parameters = collector.type_parameters;
+    let is_type_parameter = |usage: &Usage| {
+        type_parameters.iter().any(|(name, scope)| {
+            *name == usage.name
+                && scope.start as usize <= usage.span_start
+                && usage.span_end <= scope.end as usize
+        })
+    };
+    collector
+        .usages
+        .into_iter()
+        .filter(|usage| usage.qualifier.is_some() || !is_type_parameter(usage))
+        .collect()
+}
+
+struct UsageCollector<'s> {
+    lines: &'s LineIndex<'s>,
+    filename: &'s str,
+    kind: UsageKind,
+    usages: Vec<Usage>,
+    /// The nodes currently being visited, innermost last.
+    ancestors: Vec<(AstType, Span)>,
+    /// Type parameter names with the span of the node they are declared on.
+    type_parameters: Vec<(String, Span)>,
+}
+
+impl UsageCollector<'_> {
+    fn record(&mut self, name: String, qualifier: Option<String>, kind: UsageKind, span: Span) {
+        let (line, col) = self.lines.line_col(span.start as usize);
+        self.usages.push(Usage {
+            name,
+            qualifier,
+            kind,
+            filename: self.filename.to_string(),
+            line,
+            col,
+            span_start: span.start as usize,
+            span_end: span.end as usize,
+        });
+    }
+
+    fn record_type_name(&mut self, type_name: &TSTypeName, kind: UsageKind, span: Span) {
+        match type_name {
+            TSTypeName::IdentifierReference(id) => {
+                self.record(id.name.to_string(), None, kind, span)
+            }
+            TSTypeName::QualifiedName(qualified) => self.record(
+                qualified.right.name.to_string(),
+                Some(qualified.left.to_string()),
+                kind,
+                span,
+            ),
+            TSTypeName::ThisExpression(_) => {}
+        }
+    }
+
+    fn with_kind(&mut self, kind: UsageKind, visit: impl FnOnce(&mut Self)) {
+        let outer = std::mem::replace(&mut self.kind, kind);
+        visit(self);
+        self.kind = outer;
+    }
+}
+
+impl<'a> Visit<'a> for UsageCollector<'_> {
+    fn visit_ts_type_reference(&mut self, it: &TSTypeReference<'a>) {
+        self.record_type_name(&it.type_name, self.kind, it.span);
+        // Generic arguments are plain type positions again.
+        self.with_kind(UsageKind::Type, |this| {
+            if let Some(arguments) = &it.type_arguments {
+                this.visit_ts_type_parameter_instantiation(arguments);
+            }
+        });
+    }
+
+    fn visit_ts_class_implements(&mut self, it: &TSClassImplements<'a>) {
+        self.record_type_name(&it.expression, UsageKind::Implements, it.span);
+        if let Some(arguments) = &it.type_arguments {
+            self.visit_ts_type_parameter_instantiation(arguments);
+        }
+    }
+
+    fn visit_ts_interface_heritage(&mut self, it: &TSInterfaceHeritage<'a>) {
+        match &it.expression {
+            Expression::Identifier(id) => {
+                self.record(id.name.to_string(), None, UsageKind::Extends, it.span)
+            }
+            Expression::StaticMemberExpression(member) => {
+                if let Expression::Identifier(object) = &member.object {
+                    self.record(
+                        member.property.name.to_string(),
+                        Some(object.name.to_string()),
+                        UsageKind::Extends,
+                        it.span,
+                    );
+                }
+            }
+            _ => {}
+        }
+        if let Some(arguments) = &it.type_arguments {
+            self.visit_ts_type_parameter_instantiation(arguments);
+        }
+    }
+
+    fn visit_ts_as_expression(&mut self, it: &TSAsExpression<'a>) {
+        self.visit_expression(&it.expression);
+        self.with_kind(UsageKind::Assertion, |this| {
+            this.visit_ts_type(&it.type_annotation)
+        });
+    }
+
+    fn visit_ts_type_assertion(&mut self, it: &TSTypeAssertion<'a>) {
+        self.with_kind(UsageKind::Assertion, |this| {
+            this.visit_ts_type(&it.type_annotation)
+        });
+        self.visit_expression(&it.expression);
+    }
+
+    fn visit_ts_satisfies_expression(&mut self, it: &TSSatisfiesExpression<'a>) {
+        self.visit_expression(&it.expression);
+        self.with_kind(UsageKind::Satisfies, |this| {
+            this.visit_ts_type(&it.type_annotation)
+        });
+    }
+
+    fn enter_node(&mut self, kind: AstKind<'a>) {
+        self.ancestors.push((kind.ty(), kind.span()));
+    }
+
+    fn leave_node(&mut self, _kind: AstKind<'a>) {
+        self.ancestors.pop();
+    }
+
+    fn visit_ts_type_parameter(&mut self, it: &TSTypeParameter<'a>) {
+        let scope = type_parameter_scope(&self.ancestors).unwrap_or(it.span);
+        self.type_parameters.push((it.name.name.to_string(), scope));
+        walk::walk_ts_type_parameter(self, it);
+    }
+
+    fn visit_ts_mapped_type(&mut self, it: &TSMappedType<'a>) {
+        self.type_parameters
+            .push((it.key.name.to_string(), it.span));
+        walk::walk_ts_mapped_type(self, it);
+    }
+}
+
+/// The span a type parameter being visited is in scope for, given the nodes
+/// around it: the function, class, interface or alias owning its `<...>` list,
+/// or the conditional type an `infer` belongs to.
+pub(crate) fn type_parameter_scope(ancestors: &[(AstType, Span)]) -> Option<Span> {
+    let mut ancestors = ancestors.iter().rev();
+    match ancestors.next()? {
+        (AstType::TSTypeParameterDeclaration, _) => ancestors.next().map(|(_, span)| *span),
+        (AstType::TSInferType, _) => ancestors
+            .find(|(ty, _)| *ty == AstType::TSConditionalType)
+            .map(|(_, span)| *span),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_source;
+
+    fn usages(code: &str) -> Vec<(String, Option<String>, UsageKind)> {
+        parse_source(code, "test.ts", &[])
+            .usages
+            .into_iter()
+            .map(|usage| (usage.name, usage.qualifier, usage.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_collects_usage_kinds() {
+        let found = usages(
+            r#"
+            import * as ns from "./ns";
+            interface IBar extends IFoo {}
+            class A implements ns.IFoo {}
+            const a: Array<IFoo> = [];
+            const b = {} satisfies IFoo;
+            const c = {} as IFoo;
+            function f<T>(x: T): ns.IFoo { return x as any; }
+            "#,
+        );
+
+        let kind = |name: &str, qualifier: Option<&str>, kind: UsageKind| {
+            (name.to_string(), qualifier.map(str::to_string), kind)
+        };
+        assert_eq!(
+            found,
+            vec![
+                kind("IFoo", None, UsageKind::Extends),
+                kind("IFoo", Some("ns"), UsageKind::Implements),
+                kind("Array", None, UsageKind::Type),
+                kind("IFoo", None, UsageKind::Type),
+                kind("IFoo", None, UsageKind::Satisfies),
+                kind("IFoo", None, UsageKind::Assertion),
+                kind("IFoo", Some("ns"), UsageKind::Type),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_parameters_only_shadow_their_declaration() {
+        let found = usages(
+            r#"
+            interface T { id: string }
+            function f<T>(x: T): T { return x; }
+            class Box<T> { value?: T }
+            type Keys<O> = { [T in keyof O]: T };
+            type Item<A> = A extends Array<infer T> ? T : never;
+            const t: T = { id: "" };
+            "#,
+        );
+
+        let names: Vec<&str> = found.iter().map(|(name, _, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["Array", "T"]);
+    }
+}
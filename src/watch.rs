@@ -0,0 +1,386 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use tta::files::{normalize, read_source, ts_walker};
+use tta::Analyzer;
+
+use crate::report::FileCache;
+
+/// How long to keep collecting filesystem events before re-analysing, so an
+/// editor save that touches several files only triggers one report.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Files that were to be scanned but couldn't be read, with the reason.
+pub type Skipped = Vec<(String, io::Error)>;
+
+/// Watches `root` and keeps `analyzer` in sync with the files on disk, dropping
+/// changed files from `source_cache` so they're re-read when reported and
+/// keeping `skipped` up to date. Calls `on_change` after every batch of changes
+/// that touched them.
+///
+/// Files are tracked when `is_scanned`, the predicate the initial scan chose
+/// its files with, accepts them. With `walked` they also have to pass the
+/// ignore rules of the walk, as when the initial scan walked the tree rather
+/// than a tsconfig's file list.
+pub fn watch(
+    root: &str,
+    analyzer: &mut Analyzer,
+    source_cache: &mut FileCache,
+    skipped: &mut Skipped,
+    is_scanned: &dyn Fn(&Path) -> bool,
+    walked: bool,
+    mut on_change: impl FnMut(&Analyzer, &mut FileCache, &Skipped),
+) -> notify::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(Path::new(root), RecursiveMode::Recursive)?;
+
+    let canonical_root = std::fs::canonicalize(root)?;
+    // Same shape as the paths `find_source_files` returns, so keys line up.
+    let root_path = normalize(Path::new(root));
+
+    while let Ok(event) = rx.recv() {
+        let mut changed: BTreeSet<PathBuf> = BTreeSet::new();
+        collect_paths(event?, &mut changed);
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect_paths(event?, &mut changed);
+        }
+
+        let mut touched = 0;
+        for path in &changed {
+            let Some(path) = scanned_path(path, &canonical_root, &root_path) else {
+                continue;
+            };
+            touched += apply_change(
+                &root_path,
+                &path,
+                analyzer,
+                source_cache,
+                skipped,
+                is_scanned,
+                walked,
+            );
+        }
+
+        if touched > 0 {
+            eprintln!(
+                "\n[watch] {} file{} changed, re-analysing...\n",
+                touched,
+                if touched == 1 { "" } else { "s" }
+            );
+            on_change(analyzer, source_cache, skipped);
+        }
+    }
+
+    Ok(())
+}
+
+/// The path the initial scan knows the file at event path `path` by, if it is
+/// below the root. Event paths are only joined onto the working directory, so
+/// they may still go through `..` or a symlink the canonical root resolved.
+fn scanned_path(path: &Path, canonical_root: &Path, root_path: &Path) -> Option<PathBuf> {
+    let path = canonicalize_existing(path);
+    let relative = path.strip_prefix(canonical_root).ok()?;
+    // A single-file root reports events for the file itself.
+    if relative.as_os_str().is_empty() {
+        Some(root_path.to_path_buf())
+    } else {
+        Some(root_path.join(relative))
+    }
+}
+
+/// Canonicalizes the longest existing ancestor of `path` and appends the rest,
+/// so removed files resolve the same way they did while they existed.
+fn canonicalize_existing(path: &Path) -> PathBuf {
+    for ancestor in path.ancestors() {
+        if let Ok(canonical) = std::fs::canonicalize(ancestor) {
+            let rest = path.strip_prefix(ancestor).unwrap_or(Path::new(""));
+            return if rest.as_os_str().is_empty() {
+                canonical
+            } else {
+                canonical.join(rest)
+            };
+        }
+    }
+    path.to_path_buf()
+}
+
+fn collect_paths(event: notify::Event, changed: &mut BTreeSet<PathBuf>) {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    changed.extend(event.paths);
+}
+
+/// Re-parses or drops whatever lives at `path` and returns how many indexed
+/// files were affected.
+fn apply_change(
+    root: &Path,
+    path: &Path,
+    analyzer: &mut Analyzer,
+    source_cache: &mut FileCache,
+    skipped: &mut Skipped,
+    is_scanned: &dyn Fn(&Path) -> bool,
+    walked: bool,
+) -> usize {
+    if is_hidden(root, path) {
+        return 0;
+    }
+
+    if path.is_dir() && (!walked || is_walked(root, path)) {
+        let mut touched = 0;
+        for entry in ts_walker(path).build().filter_map(Result::ok) {
+            let file = normalize(entry.path());
+            if entry.file_type().is_some_and(|kind| kind.is_file()) && is_scanned(&file) {
+                touched += update_file(&file.to_string_lossy(), analyzer, source_cache, skipped);
+            }
+        }
+        return touched;
+    }
+
+    let filename = path.to_string_lossy().to_string();
+    let tracked = is_scanned(path) && (!walked || is_walked(root, path));
+
+    if path.is_file() && tracked {
+        return update_file(&filename, analyzer, source_cache, skipped);
+    }
+
+    // The path is gone (or now ignored): drop it, and everything below it if it
+    // used to be a directory.
+    let prefix = format!("{}{}", filename, std::path::MAIN_SEPARATOR);
+    let under = |file: &str| file == filename || file.starts_with(&prefix);
+    let skipped_before = skipped.len();
+    skipped.retain(|(file, _)| !under(file));
+    let removed: Vec<String> = analyzer
+        .index()
+        .filenames()
+        .filter(|indexed| under(indexed))
+        .cloned()
+        .collect();
+    for file in &removed {
+        analyzer.remove_source(file);
+        source_cache.remove(file);
+    }
+    removed.len() + (skipped_before - skipped.len())
+}
+
+fn update_file(
+    filename: &str,
+    analyzer: &mut Analyzer,
+    source_cache: &mut FileCache,
+    skipped: &mut Skipped,
+) -> usize {
+    // Editors often write in several steps; a file that is briefly gone is
+    // picked up by the event for the next write.
+    let read = read_source(Path::new(filename));
+    if matches!(&read, Err(err) if err.kind() == io::ErrorKind::NotFound) {
+        return 0;
+    }
+    let was_skipped = skipped.iter().any(|(file, _)| file == filename);
+    skipped.retain(|(file, _)| file != filename);
+    let code = match read {
+        Ok(code) => code,
+        // Files that can't be read or decoded are skipped like in the initial
+        // scan, so their previous declarations go away.
+        Err(err) => {
+            eprintln!("[watch] Skipped {}: {}", filename, err);
+            let indexed = analyzer.index().filenames().any(|file| file == filename);
+            analyzer.remove_source(filename);
+            source_cache.remove(filename);
+            skipped.push((filename.to_string(), err));
+            return usize::from(indexed || !was_skipped);
+        }
+    };
+    analyzer.add_source(filename, &code);
+    source_cache.remove(filename);
+    1
+}
+
+/// Mirrors the `.hidden(true)` filter of `find_source_files` for paths below `root`.
+fn is_hidden(root: &Path, path: &Path) -> bool {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .any(|c| matches!(c, Component::Normal(name) if name.to_string_lossy().starts_with('.')))
+}
+
+/// Whether the ignore rules used by `find_source_files` would yield `path` when
+/// walking from `root`. Every directory in between has to be walked as well,
+/// since an ignored directory hides everything below it.
+fn is_walked(root: &Path, path: &Path) -> bool {
+    path.ancestors()
+        .take_while(|ancestor| *ancestor != root && ancestor.starts_with(root))
+        .all(is_walked_from_parent)
+}
+
+fn is_walked_from_parent(path: &Path) -> bool {
+    let Some(parent) = path.parent() else {
+        return false;
+    };
+    let parent = if parent.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        parent
+    };
+
+    ts_walker(parent)
+        .max_depth(Some(1))
+        .build()
+        .filter_map(Result::ok)
+        .any(|entry| normalize(entry.path()) == path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tta::files::is_source_file;
+
+    fn change(
+        root: &Path,
+        path: &Path,
+        analyzer: &mut Analyzer,
+        cache: &mut FileCache,
+        skipped: &mut Skipped,
+    ) -> usize {
+        let is_scanned = |path: &Path| is_source_file(path, false);
+        apply_change(root, path, analyzer, cache, skipped, &is_scanned, true)
+    }
+
+    fn indexed(analyzer: &Analyzer) -> Vec<String> {
+        let mut files: Vec<String> = analyzer.index().filenames().cloned().collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_add_modify_and_remove_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let (a, b) = (root.join("a.ts"), root.join("lib/b.ts"));
+        std::fs::create_dir(root.join("lib")).unwrap();
+        std::fs::write(&a, "interface User { id: string }").unwrap();
+        std::fs::write(&b, "interface User { id: string }").unwrap();
+        let mut analyzer = Analyzer::new();
+        let mut cache = FileCache::new();
+        let mut skipped = Skipped::new();
+
+        assert_eq!(change(root, &a, &mut analyzer, &mut cache, &mut skipped), 1);
+        assert_eq!(
+            change(
+                root,
+                &root.join("lib"),
+                &mut analyzer,
+                &mut cache,
+                &mut skipped
+            ),
+            1
+        );
+        assert_eq!(analyzer.findings().len(), 1);
+
+        std::fs::write(&b, "interface Account { id: string }").unwrap();
+        cache.insert(b.to_string_lossy().to_string(), String::new());
+        assert_eq!(change(root, &b, &mut analyzer, &mut cache, &mut skipped), 1);
+        assert!(analyzer.findings().is_empty());
+        assert!(cache.is_empty());
+
+        std::fs::remove_dir_all(root.join("lib")).unwrap();
+        assert_eq!(
+            change(
+                root,
+                &root.join("lib"),
+                &mut analyzer,
+                &mut cache,
+                &mut skipped
+            ),
+            1
+        );
+        assert_eq!(indexed(&analyzer), vec![a.to_string_lossy().to_string()]);
+    }
+
+    #[test]
+    fn test_undecodable_change_drops_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let (a, b) = (root.join("a.ts"), root.join("b.ts"));
+        std::fs::write(&a, "interface User { id: string }").unwrap();
+        std::fs::write(&b, "interface User { id: string }").unwrap();
+        let mut analyzer = Analyzer::new();
+        let mut cache = FileCache::new();
+        let mut skipped = Skipped::new();
+        change(root, &a, &mut analyzer, &mut cache, &mut skipped);
+        change(root, &b, &mut analyzer, &mut cache, &mut skipped);
+        assert_eq!(analyzer.findings().len(), 1);
+
+        std::fs::write(&b, b"interface User { id: \xFF }").unwrap();
+        assert_eq!(change(root, &b, &mut analyzer, &mut cache, &mut skipped), 1);
+        assert!(analyzer.findings().is_empty());
+        assert_eq!(indexed(&analyzer), vec![a.to_string_lossy().to_string()]);
+        assert_eq!(change(root, &b, &mut analyzer, &mut cache, &mut skipped), 0);
+    }
+
+    #[test]
+    fn test_is_hidden_below_root() {
+        let root = Path::new("src");
+        assert!(is_hidden(root, Path::new("src/.cache/a.ts")));
+        assert!(!is_hidden(root, Path::new("src/lib/a.ts")));
+    }
+
+    #[test]
+    fn test_scanned_path_through_parent_dirs_and_single_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("proj");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("a.ts"), "").unwrap();
+        let canonical_root = std::fs::canonicalize(&root).unwrap();
+        let root_path = Path::new("../proj");
+
+        let via_parent = root.join("sub/../a.ts");
+        assert_eq!(
+            scanned_path(&via_parent, &canonical_root, root_path),
+            Some(PathBuf::from("../proj/a.ts"))
+        );
+        let removed = root.join("sub/../gone.ts");
+        assert_eq!(
+            scanned_path(&removed, &canonical_root, root_path),
+            Some(PathBuf::from("../proj/gone.ts"))
+        );
+        assert_eq!(
+            scanned_path(&dir.path().join("other.ts"), &canonical_root, root_path),
+            None
+        );
+
+        let file = canonical_root.join("a.ts");
+        assert_eq!(
+            scanned_path(&root.join("a.ts"), &file, Path::new("a.ts")),
+            Some(PathBuf::from("a.ts"))
+        );
+    }
+
+    #[test]
+    fn test_skipped_files_follow_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let a = root.join("a.ts");
+        std::fs::write(&a, b"interface User { id: \xFF }").unwrap();
+        let mut analyzer = Analyzer::new();
+        let mut cache = FileCache::new();
+        let mut skipped = Skipped::new();
+
+        assert_eq!(change(root, &a, &mut analyzer, &mut cache, &mut skipped), 1);
+        assert_eq!(skipped.len(), 1);
+
+        std::fs::write(&a, "interface User { id: string }").unwrap();
+        assert_eq!(change(root, &a, &mut analyzer, &mut cache, &mut skipped), 1);
+        assert!(skipped.is_empty());
+
+        std::fs::write(&a, b"interface User { id: \xFF }").unwrap();
+        change(root, &a, &mut analyzer, &mut cache, &mut skipped);
+        std::fs::remove_file(&a).unwrap();
+        assert_eq!(change(root, &a, &mut analyzer, &mut cache, &mut skipped), 1);
+        assert!(skipped.is_empty());
+    }
+}
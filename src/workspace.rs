@@ -0,0 +1,370 @@
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::analyzer::Finding;
+use crate::files::ts_walker;
+
+/// A project of a monorepo, from an Nx `project.json` or a workspace package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    pub name: String,
+    /// Canonical path of the directory holding the project manifest.
+    pub root: PathBuf,
+    pub is_library: bool,
+}
+
+/// Where the occurrences of a finding live.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectScope<'a> {
+    /// Both occurrences belong to the same project.
+    Intra(&'a Project),
+    /// The occurrences belong to different projects.
+    Cross(&'a Project, &'a Project),
+    /// At least one occurrence is outside every known project.
+    Unassigned,
+}
+
+/// Projects of a monorepo, read from `nx.json`/`project.json`, `package.json`
+/// workspaces and `pnpm-workspace.yaml`.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub projects: Vec<Project>,
+}
+
+/// Manifests that mark the root of a monorepo.
+const WORKSPACE_MARKERS: [&str; 2] = ["nx.json", "pnpm-workspace.yaml"];
+
+impl Workspace {
+    /// Finds the workspace containing `start` by walking up to the first
+    /// directory with an `nx.json`, `pnpm-workspace.yaml` or a `package.json`
+    /// declaring `workspaces`.
+    pub fn discover(start: &Path) -> Option<Workspace> {
+        let start = std::fs::canonicalize(start).ok()?;
+        let root = start.ancestors().find(|dir| {
+            WORKSPACE_MARKERS
+                .iter()
+                .any(|marker| dir.join(marker).is_file())
+                || read_json(&dir.join("package.json"))
+                    .is_some_and(|package| package.get("workspaces").is_some())
+        })?;
+        Some(Workspace::load(root))
+    }
+
+    /// Reads every project below `root`.
+    pub fn load(root: &Path) -> Workspace {
+        let root = std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+
+        let nx = read_json(&root.join("nx.json"));
+        let layout = |key: &str, default: &str| {
+            nx.as_ref()
+                .and_then(|nx| nx.pointer(&format!("/workspaceLayout/{}", key)))
+                .and_then(Value::as_str)
+                .unwrap_or(default)
+                .to_string()
+        };
+        let apps_dir = root.join(layout("appsDir", "apps"));
+        let libs_dir = root.join(layout("libsDir", "libs"));
+
+        let package_globs = workspace_globs(&root);
+
+        // Keyed by root so a project.json wins over a package.json in the same folder.
+        let mut projects: BTreeMap<PathBuf, Project> = BTreeMap::new();
+
+        for entry in ts_walker(&root)
+            .filter_entry(|entry| entry.file_name() != "node_modules")
+            .build()
+            .filter_map(Result::ok)
+        {
+            let path = entry.path();
+            let Some(dir) = path.parent() else {
+                continue;
+            };
+            let file_name = entry.file_name();
+
+            if file_name == "project.json" {
+                let Some(manifest) = read_json(path) else {
+                    continue;
+                };
+                let is_library = match manifest.get("projectType").and_then(Value::as_str) {
+                    Some(project_type) => project_type == "library",
+                    None => dir.starts_with(&libs_dir),
+                };
+                projects.insert(
+                    dir.to_path_buf(),
+                    Project {
+                        name: manifest_name(&manifest, dir),
+                        root: dir.to_path_buf(),
+                        is_library,
+                    },
+                );
+            } else if file_name == "package.json" && dir != root {
+                let Ok(relative) = dir.strip_prefix(&root) else {
+                    continue;
+                };
+                if projects.contains_key(dir) || !package_globs.is_match(relative) {
+                    continue;
+                }
+                let Some(manifest) = read_json(path) else {
+                    continue;
+                };
+                projects.insert(
+                    dir.to_path_buf(),
+                    Project {
+                        name: manifest_name(&manifest, dir),
+                        root: dir.to_path_buf(),
+                        is_library: !dir.starts_with(&apps_dir),
+                    },
+                );
+            }
+        }
+
+        Workspace {
+            root,
+            projects: projects.into_values().collect(),
+        }
+    }
+
+    pub fn project(&self, name: &str) -> Option<&Project> {
+        self.projects.iter().find(|project| project.name == name)
+    }
+
+    /// The innermost project containing `filename`.
+    pub fn project_for(&self, filename: &str) -> Option<&Project> {
+        let path = std::fs::canonicalize(filename)
+            .ok()
+            .or_else(|| std::env::current_dir().ok().map(|cwd| cwd.join(filename)))?;
+        self.projects
+            .iter()
+            .filter(|project| path.starts_with(&project.root))
+            .max_by_key(|project| project.root.components().count())
+    }
+
+    pub fn scope(&self, finding: &Finding) -> ProjectScope<'_> {
+        match (
+            self.project_for(&finding.first.filename),
+            self.project_for(&finding.second.filename),
+        ) {
+            (Some(a), Some(b)) if a == b => ProjectScope::Intra(a),
+            (Some(a), Some(b)) => ProjectScope::Cross(a, b),
+            _ => ProjectScope::Unassigned,
+        }
+    }
+
+    /// Picks the library best suited to host a declaration that is duplicated
+    /// across `filenames`: libraries named like shared code are preferred, then
+    /// libraries that already hold one of the copies.
+    pub fn suggest_host<'a>(
+        &self,
+        filenames: impl IntoIterator<Item = &'a str>,
+    ) -> Option<&Project> {
+        let involved: Vec<&Project> = filenames
+            .into_iter()
+            .filter_map(|filename| self.project_for(filename))
+            .collect();
+
+        self.projects
+            .iter()
+            .filter(|project| project.is_library)
+            .max_by_key(|project| {
+                let shared_name = is_shared_name(&project.name) as u8;
+                let holds_copy = involved.contains(project) as u8;
+                // Higher is better; prefer the shortest (most general) name on ties.
+                (
+                    shared_name * 2 + holds_copy,
+                    std::cmp::Reverse(project.name.len()),
+                )
+            })
+            .filter(|project| is_shared_name(&project.name) || involved.contains(project))
+    }
+}
+
+/// Keeps only the findings with an occurrence in `project`.
+pub fn retain_project(findings: &mut Vec<Finding>, workspace: &Workspace, project: &Project) {
+    findings.retain(|finding| {
+        workspace.project_for(&finding.first.filename) == Some(project)
+            || workspace.project_for(&finding.second.filename) == Some(project)
+    });
+}
+
+fn is_shared_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ["shared", "common", "types", "core", "util"]
+        .iter()
+        .any(|hint| name.contains(hint))
+}
+
+fn manifest_name(manifest: &Value, dir: &Path) -> String {
+    manifest
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            dir.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Package folder globs from `package.json` workspaces and `pnpm-workspace.yaml`.
+/// Negated patterns (`!packages/internal`) are left out.
+fn workspace_globs(root: &Path) -> GlobSet {
+    let mut patterns: Vec<String> = Vec::new();
+
+    if let Some(package) = read_json(&root.join("package.json")) {
+        // Either `"workspaces": [...]` or the yarn form `"workspaces": { "packages": [...] }`.
+        let workspaces = package.get("workspaces");
+        let list = workspaces.and_then(Value::as_array).or_else(|| {
+            workspaces
+                .and_then(|w| w.get("packages"))
+                .and_then(Value::as_array)
+        });
+        if let Some(list) = list {
+            patterns.extend(list.iter().filter_map(Value::as_str).map(str::to_string));
+        }
+    }
+
+    if let Ok(yaml) = std::fs::read_to_string(root.join("pnpm-workspace.yaml")) {
+        patterns.extend(pnpm_packages(&yaml));
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        if pattern.starts_with('!') {
+            continue;
+        }
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .or_else(|_| Glob::new(pattern));
+        if let Ok(glob) = glob {
+            builder.add(glob);
+        }
+    }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
+}
+
+/// Reads the `packages:` list of a `pnpm-workspace.yaml`, which is all tta needs
+/// from it.
+fn pnpm_packages(yaml: &str) -> Vec<String> {
+    let mut packages = Vec::new();
+    let mut in_packages = false;
+
+    for line in yaml.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = trimmed.trim_end_matches(':') == "packages";
+            continue;
+        }
+        if let Some(item) = trimmed.strip_prefix('-').filter(|_| in_packages) {
+            let item = item.split(" #").next().unwrap_or(item).trim();
+            packages.push(item.trim_matches(['\'', '"']).to_string());
+        }
+    }
+
+    packages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_tree;
+    use crate::Analyzer;
+
+    #[test]
+    fn test_pnpm_packages() {
+        let yaml = "packages:\n  - 'packages/*'\n  - \"apps/**\" # apps\n  - '!**/test/**'\ncatalog:\n  - nope\n";
+        assert_eq!(
+            pnpm_packages(yaml),
+            vec!["packages/*", "apps/**", "!**/test/**"]
+        );
+    }
+
+    #[test]
+    fn test_load_nx_and_package_workspaces() {
+        let dir = temp_tree(&[
+            ("nx.json", "{}"),
+            ("package.json", r#"{ "workspaces": ["packages/*"] }"#),
+            (
+                "apps/web/project.json",
+                r#"{ "name": "web", "projectType": "application" }"#,
+            ),
+            (
+                "libs/shared-types/project.json",
+                r#"{ "name": "shared-types" }"#,
+            ),
+            ("packages/ui/package.json", r#"{ "name": "@acme/ui" }"#),
+            ("tools/package.json", r#"{ "name": "not-a-workspace" }"#),
+        ]);
+        let root = dir.path();
+
+        let workspace = Workspace::discover(&root.join("apps/web")).unwrap();
+        let mut names: Vec<&str> = workspace.projects.iter().map(|p| p.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["@acme/ui", "shared-types", "web"]);
+        assert!(workspace.project("shared-types").unwrap().is_library);
+        assert!(!workspace.project("web").unwrap().is_library);
+    }
+
+    #[test]
+    fn test_scope_and_suggested_host() {
+        let dir = temp_tree(&[
+            ("nx.json", "{}"),
+            (
+                "apps/web/project.json",
+                r#"{ "name": "web", "projectType": "application" }"#,
+            ),
+            ("apps/web/src/a.ts", "interface User { id: string }"),
+            ("apps/web/src/b.ts", "interface Cart { id: string }"),
+            (
+                "libs/billing/project.json",
+                r#"{ "name": "billing", "projectType": "library" }"#,
+            ),
+            ("libs/billing/src/user.ts", "interface User { id: string }"),
+            ("libs/billing/src/cart.ts", "interface Cart { id: string }"),
+            (
+                "libs/shared/project.json",
+                r#"{ "name": "shared", "projectType": "library" }"#,
+            ),
+            ("apps/web/src/c.ts", "interface Cart { id: number }"),
+        ]);
+        let root = dir.path();
+        let workspace = Workspace::load(root);
+
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.add_path(root).is_empty());
+        let findings = analyzer.findings();
+
+        let user = findings.iter().find(|f| f.name == "User").unwrap();
+        assert!(matches!(workspace.scope(user), ProjectScope::Cross(_, _)));
+        let host = workspace
+            .suggest_host([user.first.filename.as_str(), user.second.filename.as_str()])
+            .unwrap();
+        assert_eq!(host.name, "shared");
+
+        assert!(findings.iter().any(|f| f.name == "Cart"
+            && matches!(workspace.scope(f), ProjectScope::Intra(p) if p.name == "web")));
+
+        let mut web_only = findings.clone();
+        retain_project(&mut web_only, &workspace, workspace.project("web").unwrap());
+        assert_eq!(web_only.len(), findings.len());
+        let mut shared_only = findings;
+        retain_project(
+            &mut shared_only,
+            &workspace,
+            workspace.project("shared").unwrap(),
+        );
+        assert!(shared_only.is_empty());
+    }
+}